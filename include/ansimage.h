@@ -0,0 +1,120 @@
+/* C bindings for ansimage, generated by cbindgen from src/capi.rs. */
+
+#ifndef ANSIMAGE_H
+#define ANSIMAGE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a C API call.
+//
+// The error codes mirror the variants of [`AnsiImageError`], followed by
+// failures specific to the C API.
+typedef enum AnsimageStatus {
+  // The call succeeded.
+  ANSIMAGE_STATUS_OK = 0,
+  // The image could not be decoded or handled.
+  ANSIMAGE_STATUS_IMAGE = 1,
+  // The image file could not be read.
+  ANSIMAGE_STATUS_IO = 2,
+  // The settings are invalid.
+  ANSIMAGE_STATUS_INVALID_SETTINGS = 3,
+  // An internal processing step failed.
+  ANSIMAGE_STATUS_PROCESSING = 4,
+  // The conversion was cancelled.
+  ANSIMAGE_STATUS_CANCELLED = 5,
+  // A required pointer was null, a string was not valid UTF-8, or a buffer
+  // did not match its dimensions.
+  ANSIMAGE_STATUS_INVALID_ARGUMENT = 6,
+  // The library panicked. This is a bug.
+  ANSIMAGE_STATUS_PANIC = 7,
+} AnsimageStatus;
+
+// Conversion settings, created with [`ansimage_settings_new`] or
+// [`ansimage_settings_from_json`] and released with
+// [`ansimage_settings_free`].
+typedef struct AnsimageSettings AnsimageSettings;
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates settings with the default values.
+//
+// The returned handle must be released with [`ansimage_settings_free`].
+struct AnsimageSettings *ansimage_settings_new(void);
+
+// Creates settings from a JSON object with the structure of a configuration
+// file, storing the handle in `out`. Missing settings keep their defaults.
+//
+// On success, the handle must be released with [`ansimage_settings_free`].
+//
+// # Safety
+//
+// `json` must be a null-terminated string and `out` must be valid for writes.
+enum AnsimageStatus ansimage_settings_from_json(const char *json, struct AnsimageSettings **out);
+
+// Releases settings created by this library. Passing null does nothing.
+//
+// # Safety
+//
+// `settings` must be null or a handle from this library that has not been
+// released yet.
+void ansimage_settings_free(struct AnsimageSettings *settings);
+
+// Converts an image file into a styled terminal string, storing it in `out`.
+//
+// Null `settings` converts with the default settings. On success, the string
+// must be released with [`ansimage_free_string`].
+//
+// # Safety
+//
+// `path` must be a null-terminated string, `settings` must be null or a live
+// handle, and `out` must be valid for writes.
+enum AnsimageStatus ansimage_convert_file(const char *path,
+                                          const struct AnsimageSettings *settings,
+                                          char **out);
+
+// Converts a buffer of 8-bit RGBA pixels into a styled terminal string,
+// storing it in `out`.
+//
+// The pixels are stored row by row without padding, so `len` must be
+// `width * height * 4`. Null `settings` converts with the default settings.
+// On success, the string must be released with [`ansimage_free_string`].
+//
+// # Safety
+//
+// `pixels` must be valid for reads of `len` bytes, `settings` must be null or
+// a live handle, and `out` must be valid for writes.
+enum AnsimageStatus ansimage_convert_rgba_buffer(const uint8_t *pixels,
+                                                 size_t len,
+                                                 uint32_t width,
+                                                 uint32_t height,
+                                                 const struct AnsimageSettings *settings,
+                                                 char **out);
+
+// Releases a string returned by this library. Passing null does nothing.
+//
+// # Safety
+//
+// `string` must be null or a string from this library that has not been
+// released yet.
+void ansimage_free_string(char *string);
+
+// Returns a description of the last error on the calling thread, or null if
+// no call has failed yet.
+//
+// The string is owned by the library and stays valid until the next failing
+// call on the same thread.
+const char *ansimage_last_error_message(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ANSIMAGE_H */
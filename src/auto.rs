@@ -0,0 +1,165 @@
+//! Automatic selection of the best character mode for an image.
+//!
+//! The search renders an image with each predefined character set, with and
+//! without dithering when a palette is used, and keeps the combination whose
+//! output has the lowest mean perceptual color difference (see
+//! [`metrics`](crate::metrics)) from the resized source.
+
+use std::path::Path;
+
+use image::DynamicImage;
+
+use crate::{
+    converter, error, metrics,
+    parallel::prelude::*,
+    settings::{AsciiCharSet, CharacterMode, Settings, UnicodeCharSet},
+};
+
+/// The character modes tried by the search, in order of preference for ties.
+const CANDIDATE_MODES: [CharacterMode; 5] = [
+    CharacterMode::Unicode(UnicodeCharSet::Quarter),
+    CharacterMode::Unicode(UnicodeCharSet::Half),
+    CharacterMode::Unicode(UnicodeCharSet::Full),
+    CharacterMode::Unicode(UnicodeCharSet::Shade),
+    CharacterMode::Ascii(AsciiCharSet::All),
+];
+
+/// Converts an image file with the character mode and dithering setting that
+/// represent it best, and returns the output with the chosen [`Settings`].
+///
+/// See [`convert_image_auto`] for details.
+///
+/// # Errors
+///
+/// This function can fail for the same reasons as [`convert`](crate::convert).
+pub fn convert_auto(path: &Path, settings: &Settings) -> error::Result<(String, Settings)> {
+    let img = crate::load_image(path, settings.transform.auto_orient)?;
+    convert_image_auto(&img, settings)
+}
+
+/// Converts a pre-loaded image with the character mode and dithering setting
+/// that represent it best, and returns the output with the chosen [`Settings`].
+///
+/// All other settings, including the size and colors, are taken from
+/// `settings`. Dithering is only searched when a palette is used. Each
+/// candidate is scored by the mean color difference between the image the
+/// characters display and the resized source, and the lowest score wins.
+///
+/// # Errors
+///
+/// This function can fail for the same reasons as
+/// [`convert_image`](crate::convert_image), if every candidate fails.
+pub fn convert_image_auto(
+    img: &DynamicImage,
+    settings: &Settings,
+) -> error::Result<(String, Settings)> {
+    let dithering: &[bool] = if settings.colors.is_truecolor {
+        &[settings.advanced.dithering.is_enabled]
+    } else {
+        &[false, true]
+    };
+    let candidates: Vec<Settings> = CANDIDATE_MODES
+        .iter()
+        .flat_map(|mode| {
+            dithering.iter().map(|&is_enabled| {
+                let mut candidate = settings.clone();
+                candidate.characters.mode = mode.clone();
+                candidate.advanced.dithering.is_enabled = is_enabled;
+                candidate
+            })
+        })
+        .collect();
+
+    // Candidates the settings do not allow, such as two-color ASCII with a
+    // one-color palette, are skipped. The search only fails if none succeed.
+    let results: Vec<_> = candidates
+        .par_iter()
+        .map(|candidate| metrics::measure_grid(img, candidate))
+        .collect();
+    let mut first_error = None;
+    let mut measured = Vec::with_capacity(results.len());
+    for (candidate, result) in candidates.into_iter().zip(results) {
+        match result {
+            Ok((metrics, grid)) => measured.push((metrics, grid, candidate)),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    let best = measured
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1.0.delta_e.total_cmp(&b.1.0.delta_e))
+        .map(|(i, _)| i);
+    let Some(best) = best else {
+        return Err(first_error.expect("there is at least one candidate"));
+    };
+
+    // Keep the winning grid instead of converting the image again.
+    let (_, grid, chosen) = measured.swap_remove(best);
+    let canvas = converter::decorate(grid.canvas, grid.box_size, &chosen);
+    Ok((canvas.render(chosen.advanced.compression), chosen))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    use super::convert_image_auto;
+    use crate::settings::{
+        CharacterMode, Characters, ColorMode, Colors, Settings, Size, UnicodeCharSet,
+    };
+
+    #[test]
+    fn picks_quarter_blocks_for_thin_stripes() {
+        // One-pixel stripes can only be reproduced with half-cell resolution.
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, _| {
+            if x % 4 < 1 {
+                Rgb([255; 3])
+            } else {
+                Rgb([0; 3])
+            }
+        }));
+        let settings = Settings {
+            size: Size {
+                width: 8,
+                height: 8,
+                ..Default::default()
+            },
+            characters: Characters {
+                aspect_ratio: 1.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (output, chosen) = convert_image_auto(&img, &settings).unwrap();
+        assert_eq!(
+            chosen.characters.mode,
+            CharacterMode::Unicode(UnicodeCharSet::Quarter)
+        );
+        assert_eq!(output, crate::convert_image(&img, &chosen).unwrap());
+    }
+
+    #[test]
+    fn skips_candidates_a_one_color_palette_does_not_allow() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([40; 3])));
+        let settings = Settings {
+            size: Size {
+                width: 4,
+                height: 4,
+                ..Default::default()
+            },
+            colors: Colors {
+                is_truecolor: false,
+                palette: vec![Rgb([0; 3])],
+            },
+            characters: Characters {
+                color_mode: ColorMode::TwoColor,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (_, chosen) = convert_image_auto(&img, &settings).unwrap();
+        assert!(matches!(chosen.characters.mode, CharacterMode::Unicode(_)));
+    }
+}
@@ -1,18 +1,38 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use ansimage::{
-    Advanced, Characters, Colors, Dithering, Settings, Size, UnicodeCharSet, convert, palettes,
-    settings::CharacterMode,
+    AsciiCharSet, ColorMode, Crop, DitherMatrix, EdgeDetector, EdgeStyle, Equalization, FrameStyle,
+    Montage, Rotation, Settings, SizeMode, UnicodeCharSet, compare, convert, convert_auto,
+    convert_montage, palettes,
+    settings::{CharacterMode, Overlay},
 };
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use color_eyre::eyre::{WrapErr as _, eyre};
+use fast_image_resize::FilterType;
 
 /// A simple command-line tool to convert images into terminal art.
+///
+/// Every option defaults to the library's `Settings::default()`, or to the
+/// values of the given preset and configuration file.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// Path to the input image file.
-    #[arg(required = true)]
-    input: PathBuf,
+    /// Paths to the input image files. Several images are laid out as a grid.
+    #[arg(required_unless_present = "print_config")]
+    inputs: Vec<PathBuf>,
+
+    /// Load settings from a TOML or JSON file. Its values override the preset.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Load settings from `<config dir>/ansimage/presets/<NAME>.toml`, such as
+    /// `~/.config/ansimage/presets/<NAME>.toml` on Linux.
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Print the effective settings as TOML and exit, e.g. to save a preset.
+    #[arg(long)]
+    print_config: bool,
 
     /// Optional path to write the output text file.
     #[arg(short, long)]
@@ -22,48 +42,798 @@ struct Cli {
     #[arg(long)]
     quiet: bool,
 
-    /// Output width in characters.
+    /// Pick the character set and dithering that best represent the image.
+    #[arg(long, conflicts_with = "compare")]
+    auto: bool,
+
+    /// Render the image once per character set, side by side, to compare them.
+    #[arg(long, value_enum, value_delimiter = ',', num_args = 1..)]
+    compare: Vec<CliCharset>,
+
+    /// Number of images per row when converting several images [default: 3, or
+    /// the number of compared character sets].
     #[arg(long)]
-    width: Option<usize>,
+    columns: Option<usize>,
+
+    /// Blank columns between images when converting several images.
+    #[arg(long, default_value_t = Montage::default().column_gap)]
+    gutter: usize,
 
-    /// Output height in characters.
+    /// Do not label images with their file names when converting several images.
     #[arg(long)]
+    no_labels: bool,
+
+    /// Output width in characters [default: 80].
+    #[arg(long, help_heading = "Size")]
+    width: Option<usize>,
+
+    /// Output height in characters [default: 40].
+    #[arg(long, help_heading = "Size")]
     height: Option<usize>,
 
+    /// How to fit the image into the output width and height [default: fit].
+    #[arg(long, value_enum, help_heading = "Size")]
+    size_mode: Option<CliSizeMode>,
+
+    /// Never scale images up beyond their native resolution.
+    #[arg(long, overrides_with = "upscale", help_heading = "Size")]
+    no_upscale: bool,
+
+    /// Allow scaling images up beyond their native resolution.
+    #[arg(long, help_heading = "Size")]
+    upscale: bool,
+
+    /// Pad the output to exactly the width and height, centering the image.
+    #[arg(long, overrides_with = "no_pad", help_heading = "Size")]
+    pad: bool,
+
+    /// Do not pad the output.
+    #[arg(long, help_heading = "Size")]
+    no_pad: bool,
+
+    /// Ignore the EXIF orientation of the image.
+    #[arg(long, overrides_with = "auto_orient", help_heading = "Transform")]
+    no_auto_orient: bool,
+
+    /// Apply the EXIF orientation of the image.
+    #[arg(long, help_heading = "Transform")]
+    auto_orient: bool,
+
+    /// Clockwise rotation in degrees [default: 0].
+    #[arg(long, value_enum, help_heading = "Transform")]
+    rotate: Option<CliRotation>,
+
+    /// Mirror the image horizontally.
+    #[arg(
+        long,
+        overrides_with = "no_flip_horizontal",
+        help_heading = "Transform"
+    )]
+    flip_horizontal: bool,
+
+    /// Do not mirror the image horizontally.
+    #[arg(long, help_heading = "Transform")]
+    no_flip_horizontal: bool,
+
+    /// Mirror the image vertically.
+    #[arg(long, overrides_with = "no_flip_vertical", help_heading = "Transform")]
+    flip_vertical: bool,
+
+    /// Do not mirror the image vertically.
+    #[arg(long, help_heading = "Transform")]
+    no_flip_vertical: bool,
+
+    /// Render only a region of the image, in source pixels.
+    #[arg(long, value_name = "X,Y,WIDTH,HEIGHT", value_parser = parse_crop, help_heading = "Transform")]
+    crop: Option<Crop>,
+
+    /// Zoom factor within the image or crop region, at least 1.0 [default: 1.0].
+    #[arg(long, help_heading = "Transform")]
+    zoom: Option<f32>,
+
+    /// Horizontal pan of the zoomed view, from -1.0 to 1.0 [default: 0.0].
+    #[arg(long, allow_negative_numbers = true, help_heading = "Transform")]
+    pan_x: Option<f32>,
+
+    /// Vertical pan of the zoomed view, from -1.0 to 1.0 [default: 0.0].
+    #[arg(long, allow_negative_numbers = true, help_heading = "Transform")]
+    pan_y: Option<f32>,
+
+    /// The character set to draw with [default: ascii].
+    #[arg(long, value_enum, help_heading = "Characters")]
+    charset: Option<CliCharset>,
+
+    /// Custom characters to draw with, sorted from darkest to brightest.
+    #[arg(long, conflicts_with = "charset", help_heading = "Characters")]
+    chars: Option<String>,
+
+    /// Whether to color only the characters or their backgrounds too [default: two].
+    #[arg(long, value_enum, help_heading = "Characters")]
+    color_mode: Option<CliColorMode>,
+
+    /// Width-to-height ratio of a terminal character [default: 0.5].
+    #[arg(long, help_heading = "Characters")]
+    aspect_ratio: Option<f32>,
+
+    /// Outline strong edges with line characters (ASCII and custom sets only).
+    #[arg(long, overrides_with = "no_edges", help_heading = "Characters")]
+    edges: bool,
+
+    /// Do not outline edges.
+    #[arg(long, help_heading = "Characters")]
+    no_edges: bool,
+
+    /// The edge detection algorithm [default: sobel].
+    #[arg(long, value_enum, help_heading = "Characters")]
+    edge_detector: Option<CliEdgeDetector>,
+
+    /// Minimum edge strength, from 0.0 to 1.0 [default: 0.25].
+    #[arg(long, help_heading = "Characters")]
+    edge_threshold: Option<f32>,
+
+    /// The characters used to draw edges [default: ascii].
+    #[arg(long, value_enum, help_heading = "Characters")]
+    edge_style: Option<CliEdgeStyle>,
+
+    /// Output 24-bit colors instead of palette colors [default: true].
+    #[arg(long, value_name = "BOOL", help_heading = "Colors")]
+    truecolor: Option<bool>,
+
+    /// Quantize to a predefined palette. Turns truecolor off unless `--truecolor`
+    /// is given.
+    #[arg(long, value_enum, help_heading = "Colors")]
+    palette: Option<CliPalette>,
+
+    /// Brightness offset, from -1.0 to 1.0 [default: 0.0].
+    #[arg(long, allow_negative_numbers = true, help_heading = "Adjustments")]
+    brightness: Option<f32>,
+
+    /// Contrast multiplier [default: 1.0].
+    #[arg(long, help_heading = "Adjustments")]
+    contrast: Option<f32>,
+
+    /// Gamma correction exponent [default: 1.0].
+    #[arg(long, help_heading = "Adjustments")]
+    gamma: Option<f32>,
+
+    /// Saturation multiplier [default: 1.0].
+    #[arg(long, help_heading = "Adjustments")]
+    saturation: Option<f32>,
+
+    /// Hue rotation in degrees [default: 0.0].
+    #[arg(long, allow_negative_numbers = true, help_heading = "Adjustments")]
+    hue_rotation: Option<f32>,
+
+    /// Invert all colors.
+    #[arg(long, overrides_with = "no_invert", help_heading = "Adjustments")]
+    invert: bool,
+
+    /// Do not invert colors.
+    #[arg(long, help_heading = "Adjustments")]
+    no_invert: bool,
+
+    /// Convert the image to grayscale.
+    #[arg(long, overrides_with = "no_grayscale", help_heading = "Adjustments")]
+    grayscale: bool,
+
+    /// Keep the image in color.
+    #[arg(long, help_heading = "Adjustments")]
+    no_grayscale: bool,
+
+    /// Lightness histogram equalization [default: none].
+    #[arg(long, value_enum, help_heading = "Adjustments")]
+    equalize: Option<CliEqualization>,
+
+    /// CLAHE clip limit, as a multiple of the average histogram bin.
+    #[arg(long, default_value_t = 3.0, help_heading = "Adjustments")]
+    clahe_clip_limit: f32,
+
+    /// CLAHE tiles along each axis.
+    #[arg(long, default_value_t = 8, help_heading = "Adjustments")]
+    clahe_tiles: u32,
+
+    /// Draw text in the top-left corner of the output.
+    #[arg(long, help_heading = "Decorations")]
+    label: Option<String>,
+
+    /// Remove the labels of a preset or configuration file.
+    #[arg(long, help_heading = "Decorations")]
+    no_label: bool,
+
+    /// Draw a border around the output.
+    #[arg(
+        long,
+        value_enum,
+        overrides_with = "no_frame",
+        help_heading = "Decorations"
+    )]
+    frame: Option<CliFrameStyle>,
+
+    /// Do not draw a border.
+    #[arg(long, help_heading = "Decorations")]
+    no_frame: bool,
+
+    /// A title for the top edge of the border.
+    #[arg(long, help_heading = "Decorations")]
+    title: Option<String>,
+
+    /// A caption for the bottom edge of the border.
+    #[arg(long, help_heading = "Decorations")]
+    caption: Option<String>,
+
+    /// The resampling filter used for resizing [default: lanczos3].
+    #[arg(long, value_enum, help_heading = "Advanced")]
+    resize_filter: Option<CliResizeFilter>,
+
+    /// Resize and average colors in linear light.
+    #[arg(long, overrides_with = "no_linear_light", help_heading = "Advanced")]
+    linear_light: bool,
+
+    /// Resize and average colors in sRGB.
+    #[arg(long, help_heading = "Advanced")]
+    no_linear_light: bool,
+
+    /// Sharpen the resized image with the given strength, such as 0.5.
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        overrides_with = "no_sharpen",
+        help_heading = "Advanced"
+    )]
+    sharpen: Option<f32>,
+
+    /// Do not sharpen the resized image.
+    #[arg(long, help_heading = "Advanced")]
+    no_sharpen: bool,
+
+    /// Dither palette colors [default: true].
+    #[arg(long, value_name = "BOOL", help_heading = "Advanced")]
+    dithering: Option<bool>,
+
+    /// The dithering algorithm [default: floyd-steinberg].
+    #[arg(long, value_enum, help_heading = "Advanced")]
+    dither_matrix: Option<CliDitherMatrix>,
+
     /// Uncompressed output (no ANSI color code compression).
-    #[arg(short, long)]
+    #[arg(short, long, overrides_with = "compressed", help_heading = "Advanced")]
     uncompressed: bool,
+
+    /// Compress repeated ANSI color codes.
+    #[arg(long, help_heading = "Advanced")]
+    compressed: bool,
+
+    /// The number of threads to convert with [default: 0, one per core].
+    #[arg(long, value_name = "COUNT", help_heading = "Advanced")]
+    threads: Option<usize>,
 }
 
-fn main() -> color_eyre::Result<()> {
-    let cli = Cli::parse();
+impl Cli {
+    /// Applies the options that were given on the command line to `settings`.
+    fn apply(&self, settings: &mut Settings) {
+        let size = &mut settings.size;
+        set(&mut size.width, self.width);
+        set(&mut size.height, self.height);
+        set(&mut size.mode, self.size_mode.map(Into::into));
+        flag(&mut size.allow_upscale, self.upscale, self.no_upscale);
+        flag(&mut size.padding.is_enabled, self.pad, self.no_pad);
+
+        let transform = &mut settings.transform;
+        flag(
+            &mut transform.auto_orient,
+            self.auto_orient,
+            self.no_auto_orient,
+        );
+        set(&mut transform.rotation, self.rotate.map(Into::into));
+        flag(
+            &mut transform.flip_horizontal,
+            self.flip_horizontal,
+            self.no_flip_horizontal,
+        );
+        flag(
+            &mut transform.flip_vertical,
+            self.flip_vertical,
+            self.no_flip_vertical,
+        );
+        set(&mut transform.crop, self.crop);
+        set(&mut transform.zoom, self.zoom);
+        set(&mut transform.pan_x, self.pan_x);
+        set(&mut transform.pan_y, self.pan_y);
+
+        let characters = &mut settings.characters;
+        set(&mut characters.mode, self.charset.map(Into::into));
+        if let Some(chars) = &self.chars {
+            characters.mode = CharacterMode::Custom(chars.chars().collect());
+        }
+        set(&mut characters.color_mode, self.color_mode.map(Into::into));
+        set(&mut characters.aspect_ratio, self.aspect_ratio);
+        let edges = &mut characters.edges;
+        flag(&mut edges.is_enabled, self.edges, self.no_edges);
+        set(&mut edges.detector, self.edge_detector.map(Into::into));
+        set(&mut edges.threshold, self.edge_threshold);
+        set(&mut edges.style, self.edge_style.map(Into::into));
+
+        let colors = &mut settings.colors;
+        if let Some(palette) = self.palette {
+            colors.palette = palette.colors().to_vec();
+            colors.is_truecolor = false;
+        }
+        set(&mut colors.is_truecolor, self.truecolor);
 
-    let settings = Settings {
-        size: Size {
-            width: cli.width.unwrap_or(80),
-            height: cli.height.unwrap_or(80),
-            ..Default::default()
-        },
-        characters: Characters {
-            mode: CharacterMode::Unicode(UnicodeCharSet::Quarter),
-            ..Default::default()
-        },
-        colors: Colors {
-            palette: palettes::COLOR_PALETTE_SWEETIE16.to_vec(),
-            is_truecolor: false,
-        },
-        advanced: Advanced {
-            dithering: Dithering {
-                is_enabled: false,
+        let adjustments = &mut settings.adjustments;
+        set(&mut adjustments.brightness, self.brightness);
+        set(&mut adjustments.contrast, self.contrast);
+        set(&mut adjustments.gamma, self.gamma);
+        set(&mut adjustments.saturation, self.saturation);
+        set(&mut adjustments.hue_rotation, self.hue_rotation);
+        flag(&mut adjustments.invert, self.invert, self.no_invert);
+        flag(
+            &mut adjustments.grayscale,
+            self.grayscale,
+            self.no_grayscale,
+        );
+        set(
+            &mut adjustments.equalization,
+            self.equalize.map(|e| match e {
+                CliEqualization::None => Equalization::None,
+                CliEqualization::Global => Equalization::Global,
+                CliEqualization::Clahe => Equalization::Clahe {
+                    clip_limit: self.clahe_clip_limit,
+                    tiles: self.clahe_tiles,
+                },
+            }),
+        );
+
+        if self.no_label {
+            settings.overlays.clear();
+        }
+        if let Some(text) = &self.label {
+            settings.overlays.push(Overlay {
+                text: text.clone(),
                 ..Default::default()
-            },
-            compression: !cli.uncompressed,
-            ..Default::default()
-        },
+            });
+        }
+        if let Some(style) = self.frame {
+            settings.frame.is_enabled = true;
+            settings.frame.style = style.into();
+        }
+        if self.no_frame {
+            settings.frame.is_enabled = false;
+        }
+        set(&mut settings.frame.title, self.title.clone().map(Some));
+        set(&mut settings.frame.caption, self.caption.clone().map(Some));
+
+        let advanced = &mut settings.advanced;
+        set(
+            &mut advanced.resize_filter,
+            self.resize_filter.map(Into::into),
+        );
+        flag(
+            &mut advanced.linear_light,
+            self.linear_light,
+            self.no_linear_light,
+        );
+        if let Some(amount) = self.sharpen {
+            advanced.sharpen.is_enabled = true;
+            advanced.sharpen.amount = amount;
+        }
+        if self.no_sharpen {
+            advanced.sharpen.is_enabled = false;
+        }
+        set(&mut advanced.dithering.is_enabled, self.dithering);
+        set(
+            &mut advanced.dithering.matrix,
+            self.dither_matrix.map(Into::into),
+        );
+        flag(
+            &mut advanced.compression,
+            self.compressed,
+            self.uncompressed,
+        );
+        set(&mut advanced.threads, self.threads);
+    }
+}
+
+/// Parses a pixel crop region given as `X,Y,WIDTH,HEIGHT`.
+fn parse_crop(s: &str) -> Result<Crop, String> {
+    let values = s
+        .split(',')
+        .map(|v| v.trim().parse::<u32>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [x, y, width, height] = values[..] else {
+        return Err("expected four comma-separated numbers".into());
     };
+    Ok(Crop::Pixels {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// Overwrites `target` if a value was given.
+fn set<T>(target: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *target = value;
+    }
+}
+
+/// Turns `target` on or off if the flag or its negation was given.
+///
+/// The two flags override each other, so at most one of them is set.
+const fn flag(target: &mut bool, on: bool, off: bool) {
+    if on {
+        *target = true;
+    } else if off {
+        *target = false;
+    }
+}
+
+/// Command-line names for [`SizeMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliSizeMode {
+    /// Fit within the width and height, preserving aspect ratio.
+    Fit,
+    /// Cover the width and height, cropping the overflow.
+    Fill,
+    /// Stretch to the exact width and height.
+    Exact,
+    /// Use the width only, deriving the height.
+    Width,
+    /// Use the height only, deriving the width.
+    Height,
+}
+
+impl From<CliSizeMode> for SizeMode {
+    fn from(mode: CliSizeMode) -> Self {
+        match mode {
+            CliSizeMode::Fit => Self::Fit,
+            CliSizeMode::Fill => Self::Fill,
+            CliSizeMode::Exact => Self::Exact,
+            CliSizeMode::Width => Self::Width,
+            CliSizeMode::Height => Self::Height,
+        }
+    }
+}
+
+/// Command-line names for [`Rotation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliRotation {
+    /// No rotation.
+    #[value(name = "0")]
+    None,
+    /// 90 degrees clockwise.
+    #[value(name = "90")]
+    Rotate90,
+    /// 180 degrees.
+    #[value(name = "180")]
+    Rotate180,
+    /// 270 degrees clockwise.
+    #[value(name = "270")]
+    Rotate270,
+}
+
+impl From<CliRotation> for Rotation {
+    fn from(rotation: CliRotation) -> Self {
+        match rotation {
+            CliRotation::None => Self::None,
+            CliRotation::Rotate90 => Self::Rotate90,
+            CliRotation::Rotate180 => Self::Rotate180,
+            CliRotation::Rotate270 => Self::Rotate270,
+        }
+    }
+}
+
+/// Command-line names for the predefined [`CharacterMode`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliCharset {
+    /// All ASCII characters from the brightness ramp.
+    Ascii,
+    /// ASCII characters except the space.
+    AsciiNoSpace,
+    /// Alphabetical ASCII characters only.
+    AsciiAz,
+    /// Numeric ASCII characters only.
+    AsciiNums,
+    /// Special ASCII characters only.
+    AsciiSpec,
+    /// Solid Unicode blocks.
+    Full,
+    /// Unicode half blocks.
+    Half,
+    /// Unicode quarter and half blocks.
+    Quarter,
+    /// Unicode shade characters.
+    Shade,
+}
+
+impl From<CliCharset> for CharacterMode {
+    fn from(charset: CliCharset) -> Self {
+        match charset {
+            CliCharset::Ascii => Self::Ascii(AsciiCharSet::All),
+            CliCharset::AsciiNoSpace => Self::Ascii(AsciiCharSet::NoSpace),
+            CliCharset::AsciiAz => Self::Ascii(AsciiCharSet::Az),
+            CliCharset::AsciiNums => Self::Ascii(AsciiCharSet::Nums),
+            CliCharset::AsciiSpec => Self::Ascii(AsciiCharSet::Spec),
+            CliCharset::Full => Self::Unicode(UnicodeCharSet::Full),
+            CliCharset::Half => Self::Unicode(UnicodeCharSet::Half),
+            CliCharset::Quarter => Self::Unicode(UnicodeCharSet::Quarter),
+            CliCharset::Shade => Self::Unicode(UnicodeCharSet::Shade),
+        }
+    }
+}
+
+/// Command-line names for [`ColorMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliColorMode {
+    /// Foreground colors only.
+    One,
+    /// Foreground and background colors.
+    Two,
+}
+
+impl From<CliColorMode> for ColorMode {
+    fn from(mode: CliColorMode) -> Self {
+        match mode {
+            CliColorMode::One => Self::OneColor,
+            CliColorMode::Two => Self::TwoColor,
+        }
+    }
+}
+
+/// Command-line names for [`EdgeDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliEdgeDetector {
+    /// A Sobel operator.
+    Sobel,
+    /// A difference of Gaussians, for cleaner outlines.
+    Dog,
+}
+
+impl From<CliEdgeDetector> for EdgeDetector {
+    fn from(detector: CliEdgeDetector) -> Self {
+        match detector {
+            CliEdgeDetector::Sobel => Self::Sobel,
+            CliEdgeDetector::Dog => Self::DifferenceOfGaussians,
+        }
+    }
+}
+
+/// Command-line names for [`EdgeStyle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliEdgeStyle {
+    /// ASCII line characters.
+    Ascii,
+    /// Unicode box-drawing characters.
+    BoxDrawing,
+}
+
+impl From<CliEdgeStyle> for EdgeStyle {
+    fn from(style: CliEdgeStyle) -> Self {
+        match style {
+            CliEdgeStyle::Ascii => Self::Ascii,
+            CliEdgeStyle::BoxDrawing => Self::BoxDrawing,
+        }
+    }
+}
 
-    // The `convert` function handles opening and decoding the image.
-    let output_str = convert(&cli.input, &settings)?;
+/// Command-line names for the predefined palettes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliPalette {
+    /// The Sweetie 16 palette.
+    Sweetie16,
+    /// The Horror Blue Dark palette.
+    HorrorBlueDark,
+}
+
+impl CliPalette {
+    /// Returns the colors of the palette.
+    const fn colors(self) -> &'static [image::Rgb<u8>] {
+        match self {
+            Self::Sweetie16 => palettes::COLOR_PALETTE_SWEETIE16,
+            Self::HorrorBlueDark => palettes::COLOR_PALETTE_HORROR_BLUE_DARK,
+        }
+    }
+}
+
+/// Command-line names for [`Equalization`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliEqualization {
+    /// No equalization.
+    None,
+    /// Global histogram equalization.
+    Global,
+    /// Contrast-limited adaptive histogram equalization.
+    Clahe,
+}
+
+/// Command-line names for [`FrameStyle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliFrameStyle {
+    /// Thin single lines.
+    Single,
+    /// Double lines.
+    Double,
+    /// Thin lines with rounded corners.
+    Rounded,
+    /// Thick lines.
+    Heavy,
+    /// Plain ASCII characters.
+    Ascii,
+}
+
+impl From<CliFrameStyle> for FrameStyle {
+    fn from(style: CliFrameStyle) -> Self {
+        match style {
+            CliFrameStyle::Single => Self::Single,
+            CliFrameStyle::Double => Self::Double,
+            CliFrameStyle::Rounded => Self::Rounded,
+            CliFrameStyle::Heavy => Self::Heavy,
+            CliFrameStyle::Ascii => Self::Ascii,
+        }
+    }
+}
+
+/// Command-line names for the resize [`FilterType`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliResizeFilter {
+    /// Box filter (nearest neighbor when upscaling).
+    Box,
+    /// Bilinear filter.
+    Bilinear,
+    /// Hamming filter.
+    Hamming,
+    /// Catmull-Rom bicubic filter.
+    CatmullRom,
+    /// Mitchell-Netravali bicubic filter.
+    Mitchell,
+    /// Gaussian filter.
+    Gaussian,
+    /// Lanczos3 filter.
+    Lanczos3,
+}
+
+impl From<CliResizeFilter> for FilterType {
+    fn from(filter: CliResizeFilter) -> Self {
+        match filter {
+            CliResizeFilter::Box => Self::Box,
+            CliResizeFilter::Bilinear => Self::Bilinear,
+            CliResizeFilter::Hamming => Self::Hamming,
+            CliResizeFilter::CatmullRom => Self::CatmullRom,
+            CliResizeFilter::Mitchell => Self::Mitchell,
+            CliResizeFilter::Gaussian => Self::Gaussian,
+            CliResizeFilter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// Command-line names for [`DitherMatrix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliDitherMatrix {
+    /// Floyd-Steinberg.
+    FloydSteinberg,
+    /// Jarvis, Judice, and Ninke.
+    JarvisJudiceNinke,
+    /// Stucki.
+    Stucki,
+    /// Burkes.
+    Burkes,
+}
+
+impl From<CliDitherMatrix> for DitherMatrix {
+    fn from(matrix: CliDitherMatrix) -> Self {
+        match matrix {
+            CliDitherMatrix::FloydSteinberg => Self::FloydSteinberg,
+            CliDitherMatrix::JarvisJudiceNinke => Self::JarvisJudiceNinke,
+            CliDitherMatrix::Stucki => Self::Stucki,
+            CliDitherMatrix::Burkes => Self::Burkes,
+        }
+    }
+}
+
+/// Builds the settings from the defaults, a named preset and a configuration
+/// file, in increasing order of precedence.
+///
+/// Each file only needs to contain the values it changes.
+fn load_settings(preset: Option<&str>, config: Option<&Path>) -> color_eyre::Result<Settings> {
+    let preset = preset
+        .map(|name| {
+            let dir = dirs::config_dir()
+                .ok_or_else(|| eyre!("cannot locate the user configuration directory"))?;
+            Ok::<_, color_eyre::Report>(
+                dir.join("ansimage")
+                    .join("presets")
+                    .join(format!("{name}.toml")),
+            )
+        })
+        .transpose()?;
+
+    let mut merged = serde_json::Value::Object(serde_json::Map::new());
+    for path in preset.iter().map(PathBuf::as_path).chain(config) {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let value: serde_json::Value = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&text)?
+        } else {
+            toml::from_str(&text)?
+        };
+        merge(&mut merged, value);
+    }
+    serde_json::from_value(merged).wrap_err("invalid settings")
+}
+
+/// Recursively merges `overlay` into `base`, replacing everything but tables.
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn main() -> color_eyre::Result<()> {
+    let cli = Cli::parse();
+
+    let mut settings = load_settings(cli.preset.as_deref(), cli.config.as_deref())?;
+    cli.apply(&mut settings);
+
+    if cli.print_config {
+        print!("{}", toml::to_string(&settings)?);
+        return Ok(());
+    }
+
+    // The `convert` functions handle opening and decoding the images.
+    let montage = Montage {
+        columns: cli.columns.unwrap_or(if cli.compare.is_empty() {
+            Montage::default().columns
+        } else {
+            cli.compare.len()
+        }),
+        column_gap: cli.gutter,
+        labels: !cli.no_labels,
+        ..Default::default()
+    };
+    let output_str = match cli.inputs.as_slice() {
+        [input] if !cli.compare.is_empty() => {
+            let variants: Vec<(String, Settings)> = cli
+                .compare
+                .iter()
+                .map(|&charset| {
+                    let name = charset
+                        .to_possible_value()
+                        .map_or_else(|| format!("{charset:?}"), |v| v.get_name().to_string());
+                    let mut variant = settings.clone();
+                    variant.characters.mode = charset.into();
+                    (name, variant)
+                })
+                .collect();
+            compare(input, &variants, &montage)?.render(settings.advanced.compression)
+        }
+        [input] if cli.auto => {
+            let (output, chosen) = convert_auto(input, &settings)?;
+            if !cli.quiet {
+                eprintln!(
+                    "Chose {:?} with dithering {}.",
+                    chosen.characters.mode,
+                    if chosen.advanced.dithering.is_enabled {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                );
+            }
+            output
+        }
+        [input] => convert(input, &settings)?,
+        _ if !cli.compare.is_empty() || cli.auto => {
+            color_eyre::eyre::bail!("--compare and --auto take exactly one input image")
+        }
+        inputs => {
+            convert_montage(inputs, &settings, &montage)?.render(settings.advanced.compression)
+        }
+    };
 
     if !cli.quiet {
         println!("{output_str}");
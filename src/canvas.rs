@@ -0,0 +1,504 @@
+//! A grid of styled character cells and its ANSI renderer.
+//!
+//! Conversion first produces a [`Canvas`], which can then be padded, composited,
+//! or otherwise edited at the cell level before being rendered into a string of
+//! ANSI escape sequences.
+
+use std::fmt::Write as _;
+
+use image::Rgb;
+
+use crate::{
+    parallel::prelude::*,
+    settings::{Alignment, Frame, Overlay, PaddingFill},
+};
+
+/// A single character cell with optional foreground and background colors.
+///
+/// A `None` color uses the terminal's default color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    /// The character drawn in the cell.
+    pub character: char,
+    /// The foreground (character) color.
+    pub fg: Option<Rgb<u8>>,
+    /// The background color.
+    pub bg: Option<Rgb<u8>>,
+}
+
+impl Cell {
+    /// A blank cell using the terminal's default colors.
+    pub const BLANK: Self = Self {
+        character: ' ',
+        fg: None,
+        bg: None,
+    };
+
+    /// Creates a blank cell with a solid background color.
+    #[must_use]
+    pub const fn solid(color: Rgb<u8>) -> Self {
+        Self {
+            character: ' ',
+            fg: None,
+            bg: Some(color),
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::BLANK
+    }
+}
+
+/// A rectangular grid of character cells.
+///
+/// Each position holds either a [`Cell`] or nothing. Empty positions are
+/// transparent: they are skipped when compositing one canvas onto another, and
+/// rendered as cursor movements so existing terminal content shows through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<Cell>>,
+}
+
+impl Canvas {
+    /// Creates a canvas where every position holds `fill` (`None` for transparent).
+    #[must_use]
+    pub fn new(width: usize, height: usize, fill: Option<Cell>) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Creates a canvas from rows of cells.
+    ///
+    /// The canvas is as wide as the longest row; shorter rows are padded with
+    /// transparent positions.
+    #[must_use]
+    pub fn from_rows(rows: Vec<Vec<Cell>>) -> Self {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for row in rows {
+            let len = row.len();
+            cells.extend(row.into_iter().map(Some));
+            cells.extend(std::iter::repeat_n(None, width - len));
+        }
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Returns the width of the canvas in cells.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the canvas in cells.
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the cell at a position, or `None` if it is transparent or out of bounds.
+    #[must_use]
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        if x >= self.width {
+            return None;
+        }
+        self.cells.get(y * self.width + x).copied().flatten()
+    }
+
+    /// Sets the cell at a position (`None` for transparent). Out-of-bounds
+    /// positions are ignored.
+    pub fn set(&mut self, x: usize, y: usize, cell: Option<Cell>) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = cell;
+        }
+    }
+
+    /// Draws another canvas on top of this one, with its top-left corner at `(x, y)`.
+    ///
+    /// Transparent positions of `other` leave this canvas unchanged, and parts of
+    /// `other` beyond the bounds of this canvas are clipped.
+    pub fn draw(&mut self, other: &Self, x: usize, y: usize) {
+        for oy in 0..other.height {
+            for ox in 0..other.width {
+                if let Some(cell) = other.get(ox, oy) {
+                    self.set(x + ox, y + oy, Some(cell));
+                }
+            }
+        }
+    }
+
+    /// Returns a canvas of at least `width` x `height` cells, with this canvas
+    /// placed according to the alignments and the remaining space filled.
+    #[must_use]
+    pub fn padded(
+        &self,
+        width: usize,
+        height: usize,
+        horizontal: Alignment,
+        vertical: Alignment,
+        fill: PaddingFill,
+    ) -> Self {
+        let width = width.max(self.width);
+        let height = height.max(self.height);
+        let fill = match fill {
+            PaddingFill::Default => Some(Cell::BLANK),
+            PaddingFill::Color(color) => Some(Cell::solid(color)),
+            PaddingFill::Transparent => None,
+        };
+        let mut padded = Self::new(width, height, fill);
+        padded.draw(
+            self,
+            horizontal.offset(self.width, width),
+            vertical.offset(self.height, height),
+        );
+        padded
+    }
+
+    /// Draws a text [`Overlay`] on top of this canvas.
+    ///
+    /// Text extending beyond the canvas is clipped. Because the text is written
+    /// into cells, the renderer compresses its colors like any other cells.
+    pub fn overlay(&mut self, overlay: &Overlay) {
+        let lines: Vec<Vec<char>> = overlay.text.lines().map(|l| l.chars().collect()).collect();
+        let text_w = lines.iter().map(Vec::len).max().unwrap_or(0);
+        if text_w == 0 {
+            return;
+        }
+        let pad = if overlay.background.is_some() {
+            overlay.box_padding
+        } else {
+            0
+        };
+        let (block_w, block_h) = (text_w + 2 * pad, lines.len());
+
+        let anchor =
+            |alignment: Alignment, block: usize, total: usize, margin: usize| match alignment {
+                Alignment::Start => margin,
+                Alignment::Center => alignment.offset(block, total),
+                Alignment::End => total.saturating_sub(block + margin),
+            };
+        let x0 = anchor(overlay.horizontal, block_w, self.width, overlay.margin_x);
+        let y0 = anchor(overlay.vertical, block_h, self.height, overlay.margin_y);
+
+        if let Some(color) = overlay.background {
+            for y in y0..y0 + block_h {
+                for x in x0..x0 + block_w {
+                    self.set(x, y, Some(Cell::solid(color)));
+                }
+            }
+        }
+
+        for (dy, line) in lines.iter().enumerate() {
+            let x = x0 + pad + overlay.horizontal.offset(line.len(), text_w);
+            for (dx, &character) in line.iter().enumerate() {
+                let (cx, cy) = (x + dx, y0 + dy);
+                let bg = overlay
+                    .background
+                    .or_else(|| self.get(cx, cy).and_then(|c| c.bg));
+                self.set(
+                    cx,
+                    cy,
+                    Some(Cell {
+                        character,
+                        fg: overlay.fg,
+                        bg,
+                    }),
+                );
+            }
+        }
+    }
+
+    /// Returns a canvas with a [`Frame`] drawn around this one.
+    ///
+    /// The result is two cells wider and taller. The title and caption are
+    /// drawn into the top and bottom edges, and end in `…` if they are cut to fit
+    /// between the corners.
+    #[must_use]
+    pub fn framed(&self, frame: &Frame) -> Self {
+        let (width, height) = (self.width + 2, self.height + 2);
+        let [
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+            horizontal,
+            vertical,
+        ] = frame.style.glyphs();
+        let line = |character| Cell {
+            character,
+            fg: frame.color,
+            bg: None,
+        };
+
+        let mut framed = Self::new(width, height, None);
+        for x in 1..width - 1 {
+            framed.set(x, 0, Some(line(horizontal)));
+            framed.set(x, height - 1, Some(line(horizontal)));
+        }
+        for y in 1..height - 1 {
+            framed.set(0, y, Some(line(vertical)));
+            framed.set(width - 1, y, Some(line(vertical)));
+        }
+        framed.set(0, 0, Some(line(top_left)));
+        framed.set(width - 1, 0, Some(line(top_right)));
+        framed.set(0, height - 1, Some(line(bottom_left)));
+        framed.set(width - 1, height - 1, Some(line(bottom_right)));
+
+        // Keep at least one line glyph between the text and each corner.
+        let room = width.saturating_sub(4);
+        for (text, y) in [(&frame.title, 0), (&frame.caption, height - 1)] {
+            let Some(text) = text.as_deref().filter(|t| !t.is_empty() && room > 2) else {
+                continue;
+            };
+            let mut text: Vec<char> = text.chars().collect();
+            if text.len() + 2 > room {
+                text.truncate(room - 3);
+                text.push('…');
+            }
+            let label: Vec<char> = [' '].into_iter().chain(text).chain([' ']).collect();
+            let x = 2 + frame.text_alignment.offset(label.len(), room);
+            for (i, &character) in label.iter().enumerate() {
+                framed.set(x + i, y, Some(line(character)));
+            }
+        }
+
+        framed.draw(self, 1, 1);
+        framed
+    }
+
+    /// Renders the canvas into a string of ANSI truecolor escape sequences.
+    ///
+    /// If `compression` is `true`, color codes are only emitted when a color
+    /// changes. Every row ends with a full reset, and rows are separated by `\n`.
+    #[must_use]
+    pub fn render(&self, compression: bool) -> String {
+        if self.width == 0 {
+            return vec![""; self.height].join("\n");
+        }
+        let rows: Vec<String> = self
+            .cells
+            .par_chunks(self.width)
+            .map(|row| render_row(row, compression))
+            .collect();
+        rows.join("\n")
+    }
+
+    /// Renders the canvas as an HTML `<pre>` element, for display in a browser.
+    ///
+    /// Each run of cells with the same colors becomes one `<span>` with inline
+    /// `color` and `background-color` styles. Transparent cells are rendered as
+    /// spaces, and missing colors are left to the surrounding page.
+    #[must_use]
+    pub fn render_html(&self) -> String {
+        let mut html = String::with_capacity(self.cells.len() * 8 + 32);
+        html.push_str("<pre class=\"ansimage\">");
+        for y in 0..self.height {
+            if y > 0 {
+                html.push('\n');
+            }
+            let row = &self.cells[y * self.width..(y + 1) * self.width];
+            let mut style = (None, None);
+            let mut is_open = false;
+            for cell in row {
+                let Cell { character, fg, bg } = cell.unwrap_or(Cell {
+                    character: ' ',
+                    fg: None,
+                    bg: None,
+                });
+                if (fg, bg) != style {
+                    if is_open {
+                        html.push_str("</span>");
+                    }
+                    is_open = fg.is_some() || bg.is_some();
+                    if is_open {
+                        html.push_str("<span style=\"");
+                        if let Some(Rgb([r, g, b])) = fg {
+                            write!(html, "color:#{r:02x}{g:02x}{b:02x};").unwrap();
+                        }
+                        if let Some(Rgb([r, g, b])) = bg {
+                            write!(html, "background-color:#{r:02x}{g:02x}{b:02x};").unwrap();
+                        }
+                        html.push_str("\">");
+                    }
+                    style = (fg, bg);
+                }
+                match character {
+                    '&' => html.push_str("&amp;"),
+                    '<' => html.push_str("&lt;"),
+                    '>' => html.push_str("&gt;"),
+                    c => html.push(c),
+                }
+            }
+            if is_open {
+                html.push_str("</span>");
+            }
+        }
+        html.push_str("</pre>");
+        html
+    }
+}
+
+/// Renders a single row of cells, including the trailing reset.
+pub(crate) fn render_row(row: &[Option<Cell>], compression: bool) -> String {
+    // Pre-allocate a reasonable capacity for the row string to reduce reallocations.
+    // An average ANSI escape sequence is roughly 15 bytes.
+    let mut row_str = String::with_capacity(row.len() * 15);
+
+    // State tracking for compression of ANSI escape sequences.
+    let mut last_fg: Option<Rgb<u8>> = None;
+    let mut last_bg: Option<Rgb<u8>> = None;
+    let mut skipped = 0;
+
+    for cell in row {
+        let Some(Cell { character, fg, bg }) = *cell else {
+            // Transparent cells move the cursor without drawing anything.
+            skipped += 1;
+            continue;
+        };
+        if skipped > 0 {
+            write!(row_str, "\x1b[{skipped}C").unwrap();
+            skipped = 0;
+        }
+
+        // --- Compression ---
+
+        // Write the code if the color changed OR if compression is disabled.
+        let write_fg = fg != last_fg || !compression;
+        let write_bg = bg != last_bg || !compression;
+
+        // 1. Handle Foreground Change
+        if write_fg {
+            match fg {
+                // ANSI truecolor foreground: \x1b[38;2;R;G;Bm
+                Some(Rgb([r, g, b])) => write!(row_str, "\x1b[38;2;{r};{g};{b}m").unwrap(),
+                // Reset foreground only: \x1b[39m
+                None => write!(row_str, "\x1b[39m").unwrap(),
+            }
+            last_fg = fg;
+        }
+
+        // 2. Handle Background Change
+        if write_bg {
+            match bg {
+                // ANSI truecolor background: \x1b[48;2;R;G;Bm
+                Some(Rgb([r, g, b])) => write!(row_str, "\x1b[48;2;{r};{g};{b}m").unwrap(),
+                // Reset background only: \x1b[49m
+                None => write!(row_str, "\x1b[49m").unwrap(),
+            }
+            last_bg = bg;
+        }
+
+        // 3. Write the character
+        row_str.push(character);
+    }
+
+    // Reset everything at the end of the line so the terminal doesn't bleed colors
+    row_str.push_str("\x1b[0m");
+    row_str
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{Canvas, Cell};
+    use crate::settings::{Alignment, Frame, FrameStyle, Overlay, PaddingFill};
+
+    #[test]
+    fn padding_centers_and_renders() {
+        let red = Cell {
+            character: 'x',
+            fg: Some(Rgb([255, 0, 0])),
+            bg: None,
+        };
+        let canvas = Canvas::from_rows(vec![vec![red, red]]);
+        let padded = canvas.padded(
+            4,
+            3,
+            Alignment::Center,
+            Alignment::End,
+            PaddingFill::Transparent,
+        );
+        assert_eq!((padded.width(), padded.height()), (4, 3));
+        assert_eq!(padded.get(1, 2), Some(red));
+        assert_eq!(padded.get(0, 2), None);
+
+        let rendered = padded.render(true);
+        let last = rendered.lines().last().unwrap();
+        assert_eq!(last, "\x1b[1C\x1b[38;2;255;0;0mxx\x1b[0m");
+
+        let html = padded.render_html();
+        assert_eq!(
+            html.lines().last().unwrap(),
+            " <span style=\"color:#ff0000;\">xx</span> </pre>"
+        );
+    }
+
+    #[test]
+    fn frame_with_title() {
+        let canvas = Canvas::new(8, 1, Some(Cell::BLANK));
+        let frame = Frame {
+            is_enabled: true,
+            style: FrameStyle::Rounded,
+            title: Some("cat.png".into()),
+            text_alignment: Alignment::Start,
+            ..Default::default()
+        };
+        let rendered = canvas.framed(&frame).render(true);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "╭─ cat… ─╮\x1b[0m");
+        assert_eq!(lines[1], "│        │\x1b[0m");
+        assert_eq!(lines[2], "╰────────╯\x1b[0m");
+    }
+
+    #[test]
+    fn frame_truncates_long_title_and_caption() {
+        let canvas = Canvas::new(11, 1, Some(Cell::BLANK));
+        let frame = Frame {
+            is_enabled: true,
+            style: FrameStyle::Rounded,
+            title: Some("a very long title".into()),
+            caption: Some("caption".into()),
+            ..Default::default()
+        };
+        let rendered = canvas.framed(&frame).render(true);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "╭─ a very… ─╮\x1b[0m");
+        assert_eq!(lines[2], "╰─ caption ─╯\x1b[0m");
+    }
+
+    #[test]
+    fn overlay_keeps_background_and_compresses() {
+        let gray = Rgb([50, 50, 50]);
+        let mut canvas = Canvas::new(6, 2, Some(Cell::solid(gray)));
+        canvas.overlay(&Overlay {
+            text: "hi".into(),
+            horizontal: Alignment::End,
+            vertical: Alignment::End,
+            margin_x: 1,
+            fg: Some(Rgb([255, 255, 255])),
+            ..Default::default()
+        });
+        assert_eq!(
+            canvas.get(3, 1).map(|c| (c.character, c.bg)),
+            Some(('h', Some(gray)))
+        );
+
+        let rendered = canvas.render(true);
+        assert_eq!(
+            rendered.lines().last().unwrap(),
+            "\x1b[48;2;50;50;50m   \x1b[38;2;255;255;255mhi\x1b[39m \x1b[0m"
+        );
+    }
+}
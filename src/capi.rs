@@ -0,0 +1,299 @@
+//! C bindings for using `ansimage` from C, C++, Go and other languages.
+//!
+//! Build a `cdylib` with the `capi` feature to export these functions, for
+//! example with `cargo rustc --lib --crate-type cdylib --features capi`, and
+//! include `include/ansimage.h`. Every function reports failure with an
+//! [`AnsimageStatus`], and a description of the last error on the calling
+//! thread is available from [`ansimage_last_error_message`].
+//!
+//! Settings are created from JSON with the same structure as a configuration
+//! file, and passed to conversions through an opaque [`AnsimageSettings`]
+//! handle. Output strings are owned by the library and must be released with
+//! [`ansimage_free_string`].
+
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    ffi::{CStr, CString, c_char},
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::Path,
+    ptr,
+};
+
+use image::{DynamicImage, RgbaImage};
+
+use crate::{Settings, error::AnsiImageError};
+
+/// The result of a C API call.
+///
+/// The error codes mirror the variants of [`AnsiImageError`], followed by
+/// failures specific to the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsimageStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// The image could not be decoded or handled.
+    Image = 1,
+    /// The image file could not be read.
+    Io = 2,
+    /// The settings are invalid.
+    InvalidSettings = 3,
+    /// An internal processing step failed.
+    Processing = 4,
+    /// The conversion was cancelled.
+    Cancelled = 5,
+    /// A required pointer was null, a string was not valid UTF-8, or a buffer
+    /// did not match its dimensions.
+    InvalidArgument = 6,
+    /// The library panicked. This is a bug.
+    Panic = 7,
+}
+
+impl From<&AnsiImageError> for AnsimageStatus {
+    fn from(error: &AnsiImageError) -> Self {
+        match error {
+            AnsiImageError::Image(_) => Self::Image,
+            AnsiImageError::Io(_) => Self::Io,
+            AnsiImageError::InvalidSettings(_) => Self::InvalidSettings,
+            AnsiImageError::Processing(_) => Self::Processing,
+            AnsiImageError::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+/// Conversion settings, created with [`ansimage_settings_new`] or
+/// [`ansimage_settings_from_json`] and released with
+/// [`ansimage_settings_free`].
+pub struct AnsimageSettings(Settings);
+
+thread_local! {
+    /// The message of the last error on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Creates settings with the default values.
+///
+/// The returned handle must be released with [`ansimage_settings_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn ansimage_settings_new() -> *mut AnsimageSettings {
+    Box::into_raw(Box::new(AnsimageSettings(Settings::default())))
+}
+
+/// Creates settings from a JSON object with the structure of a configuration
+/// file, storing the handle in `out`. Missing settings keep their defaults.
+///
+/// On success, the handle must be released with [`ansimage_settings_free`].
+///
+/// # Safety
+///
+/// `json` must be a null-terminated string and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansimage_settings_from_json(
+    json: *const c_char,
+    out: *mut *mut AnsimageSettings,
+) -> AnsimageStatus {
+    guard(|| {
+        out_arg(out)?;
+        // SAFETY: the caller guarantees `json` is null or a valid C string.
+        let json = unsafe { str_arg(json, "json") }?;
+        let settings: Settings = serde_json::from_str(json)
+            .map_err(|e| AnsiImageError::InvalidSettings(e.to_string()))?;
+        settings
+            .validate()
+            .map_err(|problems| AnsiImageError::InvalidSettings(problems.join(" ")))?;
+        // SAFETY: `out` is non-null and the caller guarantees it is writable.
+        unsafe { out.write(Box::into_raw(Box::new(AnsimageSettings(settings)))) };
+        Ok(())
+    })
+}
+
+/// Releases settings created by this library. Passing null does nothing.
+///
+/// # Safety
+///
+/// `settings` must be null or a handle from this library that has not been
+/// released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansimage_settings_free(settings: *mut AnsimageSettings) {
+    if !settings.is_null() {
+        // SAFETY: the caller guarantees the handle came from `Box::into_raw`.
+        drop(unsafe { Box::from_raw(settings) });
+    }
+}
+
+/// Converts an image file into a styled terminal string, storing it in `out`.
+///
+/// Null `settings` converts with the default settings. On success, the string
+/// must be released with [`ansimage_free_string`].
+///
+/// # Safety
+///
+/// `path` must be a null-terminated string, `settings` must be null or a live
+/// handle, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansimage_convert_file(
+    path: *const c_char,
+    settings: *const AnsimageSettings,
+    out: *mut *mut c_char,
+) -> AnsimageStatus {
+    guard(|| {
+        out_arg(out)?;
+        // SAFETY: the caller guarantees `path` is null or a valid C string.
+        let path = unsafe { str_arg(path, "path") }?;
+        // SAFETY: the caller guarantees `settings` is null or a live handle.
+        let settings = unsafe { settings_arg(settings) };
+        let output = crate::convert(Path::new(path), &settings)?;
+        // SAFETY: `out` is non-null and the caller guarantees it is writable.
+        unsafe { write_string(out, output) }
+    })
+}
+
+/// Converts a buffer of 8-bit RGBA pixels into a styled terminal string,
+/// storing it in `out`.
+///
+/// The pixels are stored row by row without padding, so `len` must be
+/// `width * height * 4`. Null `settings` converts with the default settings.
+/// On success, the string must be released with [`ansimage_free_string`].
+///
+/// # Safety
+///
+/// `pixels` must be valid for reads of `len` bytes, `settings` must be null or
+/// a live handle, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansimage_convert_rgba_buffer(
+    pixels: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    settings: *const AnsimageSettings,
+    out: *mut *mut c_char,
+) -> AnsimageStatus {
+    guard(|| {
+        out_arg(out)?;
+        if pixels.is_null() {
+            return Err(invalid_argument("`pixels` is null"));
+        }
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(4));
+        if expected != Some(len) {
+            return Err(invalid_argument(&format!(
+                "a {width}x{height} RGBA buffer needs {} bytes, got {len}",
+                u128::from(width) * u128::from(height) * 4,
+            )));
+        }
+        // SAFETY: the caller guarantees `pixels` is readable for `len` bytes.
+        let pixels = unsafe { std::slice::from_raw_parts(pixels, len) };
+        let img = RgbaImage::from_raw(width, height, pixels.to_vec())
+            .ok_or_else(|| invalid_argument("the buffer does not match its dimensions"))?;
+        // SAFETY: the caller guarantees `settings` is null or a live handle.
+        let settings = unsafe { settings_arg(settings) };
+        let output = crate::convert_image(&DynamicImage::ImageRgba8(img), &settings)?;
+        // SAFETY: `out` is non-null and the caller guarantees it is writable.
+        unsafe { write_string(out, output) }
+    })
+}
+
+/// Releases a string returned by this library. Passing null does nothing.
+///
+/// # Safety
+///
+/// `string` must be null or a string from this library that has not been
+/// released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansimage_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: the caller guarantees the string came from `CString::into_raw`.
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// Returns a description of the last error on the calling thread, or null if
+/// no call has failed yet.
+///
+/// The string is owned by the library and stays valid until the next failing
+/// call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn ansimage_last_error_message() -> *const c_char {
+    LAST_ERROR.with_borrow(|e| e.as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// A failure of a C API call, with the status to return and its message.
+struct Failure(AnsimageStatus, String);
+
+impl From<AnsiImageError> for Failure {
+    fn from(error: AnsiImageError) -> Self {
+        Self((&error).into(), error.to_string())
+    }
+}
+
+/// Runs the body of a C API call, recording its error and catching panics so
+/// that they do not unwind into the caller.
+fn guard(body: impl FnOnce() -> Result<(), Failure>) -> AnsimageStatus {
+    let failure = match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => return AnsimageStatus::Ok,
+        Ok(Err(failure)) => failure,
+        Err(_) => Failure(AnsimageStatus::Panic, "ansimage panicked".to_owned()),
+    };
+    // Messages come from `Display` impls and never contain null bytes in
+    // practice; an empty message is better than none if one does.
+    let message = CString::new(failure.1).unwrap_or_default();
+    LAST_ERROR.set(Some(message));
+    failure.0
+}
+
+/// Creates an invalid-argument failure.
+fn invalid_argument(message: &str) -> Failure {
+    Failure(
+        AnsimageStatus::InvalidArgument,
+        format!("Invalid argument: {message}."),
+    )
+}
+
+/// Reads a required UTF-8 string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or a null-terminated string that outlives the call.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if ptr.is_null() {
+        return Err(invalid_argument(&format!("`{name}` is null")));
+    }
+    // SAFETY: `ptr` is non-null and the caller guarantees it is a C string.
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| invalid_argument(&format!("`{name}` is not valid UTF-8")))
+}
+
+/// Returns the settings behind a handle, or the defaults for null.
+///
+/// # Safety
+///
+/// `settings` must be null or a live handle.
+unsafe fn settings_arg<'a>(settings: *const AnsimageSettings) -> Cow<'a, Settings> {
+    // SAFETY: the caller guarantees the handle is null or live.
+    unsafe { settings.as_ref() }
+        .map_or_else(|| Cow::Owned(Settings::default()), |s| Cow::Borrowed(&s.0))
+}
+
+/// Checks that an output pointer is not null, before any work is done.
+fn out_arg<T>(out: *mut T) -> Result<(), Failure> {
+    if out.is_null() {
+        return Err(invalid_argument("`out` is null"));
+    }
+    Ok(())
+}
+
+/// Hands a string over to the caller through `out`.
+///
+/// # Safety
+///
+/// `out` must be valid for writes.
+unsafe fn write_string(out: *mut *mut c_char, string: String) -> Result<(), Failure> {
+    let string = CString::new(string)
+        .map_err(|_| AnsiImageError::Processing("output contains a null byte".to_owned()))?;
+    // SAFETY: `out` is non-null and the caller guarantees it is writable.
+    unsafe { out.write(string.into_raw()) };
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Side-by-side comparisons of one image rendered with several settings.
+//!
+//! Each variant is converted from the same source image and placed in a
+//! [`Montage`] tile with its name as a header, which makes it quick to choose
+//! between character sets, palettes, or any other settings.
+
+use std::path::Path;
+
+use image::{DynamicImage, metadata::Orientation};
+
+use crate::{
+    canvas::Canvas,
+    error, montage,
+    parallel::prelude::*,
+    settings::{Montage, Settings},
+};
+
+/// Converts an image file once per named [`Settings`] variant and lays the
+/// results out side by side, each under a header with its name.
+///
+/// The file is only decoded once. Its EXIF orientation is applied to the
+/// variants that have [`Transform::auto_orient`](crate::Transform::auto_orient) set.
+///
+/// # Errors
+///
+/// Returns an `InvalidSettings` error if `montage.columns` is zero, an error if
+/// the file cannot be decoded, or the first error encountered while converting
+/// any of the variants.
+pub fn compare(
+    path: &Path,
+    variants: &[(String, Settings)],
+    montage: &Montage,
+) -> error::Result<Canvas> {
+    montage::validate(montage)?;
+    let (img, orientation) = crate::decode_image(path)?;
+    let oriented = (orientation != Orientation::NoTransforms).then(|| {
+        let mut oriented = img.clone();
+        oriented.apply_orientation(orientation);
+        oriented
+    });
+
+    let tiles = variants
+        .par_iter()
+        .map(|(name, settings)| {
+            let img = match &oriented {
+                Some(oriented) if settings.transform.auto_orient => oriented,
+                _ => &img,
+            };
+            Ok((crate::convert_image_to_canvas(img, settings)?, name.clone()))
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+
+    Ok(layout(&tiles, montage))
+}
+
+/// Converts an in-memory image once per named [`Settings`] variant and lays
+/// the results out side by side, each under a header with its name.
+///
+/// # Errors
+///
+/// Returns an `InvalidSettings` error if `montage.columns` is zero, or the
+/// first error returned by [`convert_image_to_canvas`](crate::convert_image_to_canvas)
+/// for any variant.
+pub fn compare_image(
+    img: &DynamicImage,
+    variants: &[(String, Settings)],
+    montage: &Montage,
+) -> error::Result<Canvas> {
+    montage::validate(montage)?;
+
+    let tiles = variants
+        .par_iter()
+        .map(|(name, settings)| Ok((crate::convert_image_to_canvas(img, settings)?, name.clone())))
+        .collect::<error::Result<Vec<_>>>()?;
+
+    Ok(layout(&tiles, montage))
+}
+
+/// Lays out the variant tiles with their names as headers.
+fn layout(tiles: &[(Canvas, String)], montage: &Montage) -> Canvas {
+    montage::layout(tiles, montage, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, RgbImage};
+
+    use super::compare_image;
+    use crate::settings::{CharacterMode, Characters, Montage, Settings, Size, UnicodeCharSet};
+
+    #[test]
+    fn variants_have_headers() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(8, 8, |x, y| {
+            image::Rgb([(x * 32) as u8, (y * 32) as u8, 128])
+        }));
+        let variant = |mode| Settings {
+            size: Size {
+                width: 4,
+                height: 2,
+                ..Default::default()
+            },
+            characters: Characters {
+                mode: CharacterMode::Unicode(mode),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let variants = vec![
+            ("quarter".to_string(), variant(UnicodeCharSet::Quarter)),
+            ("half".to_string(), variant(UnicodeCharSet::Half)),
+        ];
+        let montage = Montage {
+            columns: 2,
+            column_gap: 1,
+            ..Default::default()
+        };
+        let canvas = compare_image(&img, &variants, &montage).unwrap();
+        assert_eq!((canvas.width(), canvas.height()), (9, 3));
+
+        let header: String = (0..canvas.width())
+            .map(|x| canvas.get(x, 0).unwrap().character)
+            .collect();
+        assert_eq!(header, "qua… half");
+    }
+}
@@ -0,0 +1,602 @@
+//! A reusable conversion engine for converting many images with the same settings.
+//!
+//! The free functions such as [`convert_image`](crate::convert_image) validate
+//! the settings, convert the palette and allocate a resizer, quantizer and
+//! working buffers for every image. A [`Converter`] does that work once and keeps
+//! the buffers between calls, which makes it the better choice for video frames
+//! and batch jobs.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use fast_image_resize::{
+    PixelComponentMapper, PixelType, ResizeOptions, Resizer,
+    images::{Image, ImageRef},
+};
+use image::{DynamicImage, GenericImageView as _, RgbImage};
+use imagequant::{
+    Attributes as LiqAttr, Image as LiqImage, QuantizationResult as LiqResult, RGBA as LiqRGBA,
+};
+
+use crate::{
+    canvas::{Canvas, Cell},
+    edges, error, filters, geometry,
+    nearest::PaletteIndex,
+    parallel::prelude::*,
+    processing,
+    progress::{CancellationToken, ProgressCallback},
+    settings::{CharacterMode, Settings, SizeMode},
+};
+
+/// A converted character grid, before padding, overlays and frames are added.
+pub(crate) struct Grid {
+    /// The character cells, one for each 2x2 pixel block of `source`.
+    pub(crate) canvas: Canvas,
+    /// The resized and adjusted image the cells were chosen for, before quantization.
+    pub(crate) source: RgbImage,
+    /// The width and height available to the grid, in characters.
+    pub(crate) box_size: (usize, usize),
+}
+
+/// Working buffers kept between conversions so that images of the same size
+/// can be converted without reallocating them.
+#[derive(Default)]
+struct Buffers {
+    /// The 16-bit linear-light source image, when resizing in linear light.
+    linear_src: Option<Image<'static>>,
+    /// The 16-bit linear-light resized image, when resizing in linear light.
+    linear_dst: Option<Image<'static>>,
+    /// The pixels of the resized image.
+    resized: Vec<u8>,
+    /// The resized image in the RGBA format `imagequant` expects.
+    rgba: Vec<LiqRGBA>,
+    /// The palette indices of the quantized pixels.
+    indices: Vec<u8>,
+    /// The pixels of the quantized image.
+    quantized: Vec<u8>,
+}
+
+/// A conversion engine built once from [`Settings`] and used for any number of
+/// images.
+///
+/// Creating a converter validates the settings, converts the color palette to
+/// L*u*v*, and sets up the resizer, the sRGB linearization tables and the
+/// quantizer. The buffers used while converting are kept and reused, so
+/// converting a sequence of same-sized images allocates little besides the
+/// output.
+///
+/// With [`Advanced::threads`](crate::Advanced::threads) set, the converter
+/// holds a thread pool shared with other converters of the same count. The
+/// library keeps the pools of the last few counts used, so programs that
+/// switch between many counts create new threads as older pools are released.
+/// To control the pool's lifetime, pass your own to `with_thread_pool`.
+///
+/// # Example
+///
+/// ```no_run
+/// use ansimage::{Converter, Settings};
+///
+/// fn main() -> ansimage::error::Result<()> {
+///     let mut converter = Converter::new(Settings::default())?;
+///     for i in 0..10 {
+///         let frame = image::open(format!("frames/{i:04}.png"))?;
+///         print!("{}", converter.convert_image(&frame)?);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct Converter {
+    /// The settings every image is converted with.
+    settings: Settings,
+    /// The color palette in L*u*v*, indexed for nearest-color queries, or
+    /// `None` in truecolor mode.
+    palette: Option<PaletteIndex>,
+    /// The quantizer configuration.
+    liq_attr: LiqAttr,
+    /// The color palette in the RGBA format `imagequant` expects.
+    liq_palette: Vec<LiqRGBA>,
+    /// The resizer, which caches its filter coefficients between calls.
+    resizer: Resizer,
+    /// The mapping between sRGB and linear light, if resizing in linear light.
+    srgb_mapper: Option<PixelComponentMapper>,
+    /// Working buffers reused between conversions.
+    buffers: Buffers,
+    /// The callback told about completed rows, if any.
+    progress: Option<ProgressCallback>,
+    /// The token that cancels conversions, if any.
+    cancellation: Option<CancellationToken>,
+    /// The thread pool conversions run on, or `None` for rayon's global pool.
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl Converter {
+    /// Creates a converter for the given settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidSettings` error describing every problem if the
+    /// settings are invalid.
+    pub fn new(settings: Settings) -> error::Result<Self> {
+        settings
+            .validate()
+            .map_err(|problems| error::AnsiImageError::InvalidSettings(problems.join(" ")))?;
+
+        let palette = (!settings.colors.is_truecolor)
+            .then(|| PaletteIndex::new(&processing::palette_to_luv(&settings.colors.palette)));
+        let liq_palette = settings
+            .colors
+            .palette
+            .iter()
+            .map(|p| LiqRGBA {
+                r: p[0],
+                g: p[1],
+                b: p[2],
+                a: 255,
+            })
+            .collect();
+        let srgb_mapper = settings
+            .advanced
+            .linear_light
+            .then(fast_image_resize::create_srgb_mapper);
+        #[cfg(feature = "parallel")]
+        let pool = match settings.advanced.threads {
+            0 => None,
+            threads => Some(crate::parallel::shared_pool(threads).map_err(processing_error)?),
+        };
+
+        Ok(Self {
+            settings,
+            palette,
+            liq_attr: LiqAttr::new(),
+            liq_palette,
+            resizer: Resizer::new(),
+            srgb_mapper,
+            buffers: Buffers::default(),
+            progress: None,
+            cancellation: None,
+            #[cfg(feature = "parallel")]
+            pool,
+        })
+    }
+
+    /// Sets a callback that receives the number of character rows completed
+    /// and the total number of rows while an image is converted.
+    ///
+    /// See [`ProgressCallback`] for when it is called.
+    #[must_use]
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(usize, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Sets a token that cancels conversions when it is cancelled.
+    ///
+    /// The token is checked before an image is resized and before each
+    /// character row is processed. A cancelled conversion returns
+    /// [`AnsiImageError::Cancelled`](error::AnsiImageError::Cancelled).
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Runs conversions on the given thread pool, instead of rayon's global pool
+    /// or the pool created for [`Advanced::threads`](crate::Advanced::threads).
+    ///
+    /// The pool can be shared with other converters and other rayon work.
+    #[cfg(feature = "parallel")]
+    #[must_use]
+    pub fn with_thread_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Returns the settings images are converted with.
+    #[must_use]
+    pub const fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Converts an image into a styled terminal string.
+    ///
+    /// Like [`convert_image`](crate::convert_image), the image's EXIF
+    /// orientation is not applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Processing` error if resizing or quantization fails, or a
+    /// `Cancelled` error if the conversion was cancelled.
+    pub fn convert_image(&mut self, img: &DynamicImage) -> error::Result<String> {
+        let canvas = self.convert_image_to_canvas(img)?;
+        Ok(canvas.render(self.settings.advanced.compression))
+    }
+
+    /// Converts an image into a [`Canvas`] of styled cells.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Processing` error if resizing or quantization fails, or a
+    /// `Cancelled` error if the conversion was cancelled.
+    pub fn convert_image_to_canvas(&mut self, img: &DynamicImage) -> error::Result<Canvas> {
+        let Grid {
+            canvas,
+            source,
+            box_size: (box_w, box_h),
+        } = self.convert_image_to_grid(img)?;
+        self.buffers.resized = source.into_raw();
+        Ok(decorate(canvas, (box_w, box_h), &self.settings))
+    }
+
+    /// Runs the conversion pipeline up to the character grid.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Processing` error if resizing or quantization fails, or a
+    /// `Cancelled` error if the conversion was cancelled.
+    pub(crate) fn convert_image_to_grid(&mut self, img: &DynamicImage) -> error::Result<Grid> {
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = self.pool.clone() {
+            return pool.install(|| self.run_pipeline(img));
+        }
+        self.run_pipeline(img)
+    }
+
+    /// Runs the conversion pipeline on the current thread pool.
+    fn run_pipeline(&mut self, img: &DynamicImage) -> error::Result<Grid> {
+        // 1. Settings were validated when the converter was created.
+        self.check_cancelled()?;
+
+        // 2. Calculate final output dimensions in characters (width, height) from the
+        // cropped and zoomed viewport of the source image.
+        // The image is resized to 2x this size to sample 2x2 pixel blocks for each character.
+        let settings = &self.settings;
+        let img = geometry::orient(img, &settings.transform);
+        let (img_w, img_h) = img.dimensions();
+        let viewport = geometry::viewport(img_w, img_h, &settings.transform)?;
+        let (src_w, src_h) = viewport.dimensions();
+        // A frame takes one cell on each side, so the image gets the space inside it.
+        let (box_w, box_h) = if settings.frame.is_enabled {
+            (
+                settings.size.width.saturating_sub(2),
+                settings.size.height.saturating_sub(2),
+            )
+        } else {
+            (settings.size.width, settings.size.height)
+        };
+        let (w, h) = crate::calculate_dimensions(
+            src_w,
+            src_h,
+            box_w,
+            box_h,
+            settings.size.mode,
+            settings.characters.aspect_ratio,
+            settings.size.allow_upscale,
+        );
+        // In `Fill` mode, crop the overflowing sides of the viewport so the image
+        // covers the whole character grid.
+        let viewport = if settings.size.mode == SizeMode::Fill {
+            let aspect =
+                w as f64 * f64::from(settings.characters.aspect_ratio.max(0.01)) / h as f64;
+            viewport.crop_to_aspect(aspect)
+        } else {
+            viewport
+        };
+        let target_w = (w * 2) as u32;
+        let target_h = (h * 2) as u32;
+
+        // 3. Crop and resize the image using a high-performance resizer.
+        let mut resized_buffer = self.resize(&img, &viewport, target_w, target_h)?;
+        let settings = &self.settings;
+
+        // 4. Sharpen, then apply tonal and color adjustments to the (small) resized image.
+        if settings.advanced.sharpen.is_enabled {
+            filters::sharpen(&mut resized_buffer, &settings.advanced.sharpen);
+        }
+        filters::apply_adjustments(&mut resized_buffer, &settings.adjustments);
+        filters::equalize(&mut resized_buffer, settings.adjustments.equalization);
+
+        // 5. Detect edges before quantization adds noise to the image.
+        let edge_map = (settings.characters.edges.is_enabled
+            && !matches!(settings.characters.mode, CharacterMode::Unicode(_)))
+        .then(|| edges::EdgeMap::detect(&resized_buffer, w, h, &settings.characters.edges));
+
+        // 6. Optionally apply color quantization and dithering if not in truecolor mode.
+        let quantized = if settings.colors.is_truecolor {
+            None
+        } else {
+            Some(self.quantize(&resized_buffer)?)
+        };
+        let processed_img = quantized.as_ref().unwrap_or(&resized_buffer);
+
+        // 7. Process the image pixels into styled characters in parallel, reporting
+        // progress and stopping early if cancelled.
+        let (settings, palette) = (&self.settings, self.palette.as_ref());
+        let (progress, cancellation) = (self.progress.as_deref(), self.cancellation.as_ref());
+        let completed = AtomicUsize::new(0);
+        let rows: Option<Vec<Vec<Cell>>> = (0..h)
+            .into_par_iter()
+            .map(|y| {
+                if cancellation.is_some_and(CancellationToken::is_cancelled) {
+                    return None;
+                }
+                let row = processing::process_row_with_palette(
+                    y,
+                    w,
+                    processed_img,
+                    settings,
+                    palette,
+                    edge_map.as_ref(),
+                );
+                if let Some(progress) = progress {
+                    progress(completed.fetch_add(1, Ordering::Relaxed) + 1, h);
+                }
+                Some(row)
+            })
+            .collect();
+        let rows = rows.ok_or(error::AnsiImageError::Cancelled)?;
+
+        if let Some(quantized) = quantized {
+            self.buffers.quantized = quantized.into_raw();
+        }
+        Ok(Grid {
+            canvas: Canvas::from_rows(rows),
+            source: resized_buffer,
+            box_size: (box_w, box_h),
+        })
+    }
+
+    /// Returns a `Cancelled` error if the cancellation token has been cancelled.
+    fn check_cancelled(&self) -> error::Result<()> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(error::AnsiImageError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Resizes the `viewport` region of an image to the given pixel dimensions
+    /// into the reused resize buffer.
+    ///
+    /// If [`Advanced::linear_light`](crate::Advanced::linear_light) is set, the
+    /// image is converted to 16-bit linear RGB before resizing and back to sRGB
+    /// afterwards, so that averaging happens on physical light intensities
+    /// rather than gamma-encoded values.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Processing` error if the resizer or the color mapping fails.
+    fn resize(
+        &mut self,
+        img: &DynamicImage,
+        viewport: &geometry::Rect,
+        target_w: u32,
+        target_h: u32,
+    ) -> error::Result<RgbImage> {
+        let (img_w, img_h) = img.dimensions();
+        // RGB images are resized in place; anything else is converted first.
+        let converted;
+        let pixels: &[u8] = if let DynamicImage::ImageRgb8(rgb) = img {
+            rgb.as_raw()
+        } else {
+            converted = img.to_rgb8();
+            converted.as_raw()
+        };
+        let src_image =
+            ImageRef::new(img_w, img_h, pixels, PixelType::U8x3).map_err(processing_error)?;
+
+        let algorithm =
+            fast_image_resize::ResizeAlg::Convolution(self.settings.advanced.resize_filter);
+        let resize_options = ResizeOptions::new().resize_alg(algorithm).crop(
+            viewport.x,
+            viewport.y,
+            viewport.width,
+            viewport.height,
+        );
+
+        let mut buffer = std::mem::take(&mut self.buffers.resized);
+        buffer.resize(target_w as usize * target_h as usize * 3, 0);
+        let mut dst_image = Image::from_vec_u8(target_w, target_h, buffer, PixelType::U8x3)
+            .map_err(processing_error)?;
+
+        if let Some(mapper) = &self.srgb_mapper {
+            let linear_src = reuse_image(&mut self.buffers.linear_src, img_w, img_h);
+            mapper
+                .forward_map(&src_image, linear_src)
+                .map_err(processing_error)?;
+
+            let linear_dst = reuse_image(&mut self.buffers.linear_dst, target_w, target_h);
+            self.resizer
+                .resize(&*linear_src, linear_dst, Some(&resize_options))
+                .map_err(processing_error)?;
+
+            mapper
+                .backward_map(&*linear_dst, &mut dst_image)
+                .map_err(processing_error)?;
+        } else {
+            self.resizer
+                .resize(&src_image, &mut dst_image, Some(&resize_options))
+                .map_err(processing_error)?;
+        }
+
+        RgbImage::from_raw(target_w, target_h, dst_image.into_vec()).ok_or_else(|| {
+            error::AnsiImageError::Processing("Failed to create image from resized buffer.".into())
+        })
+    }
+
+    /// Reduces the image's colors to the fixed palette using `imagequant`,
+    /// dithering if enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Processing` error if any step in the `imagequant` pipeline fails.
+    fn quantize(&mut self, rgb: &RgbImage) -> error::Result<RgbImage> {
+        let (w, h) = rgb.dimensions();
+        let buffers = &mut self.buffers;
+
+        // `imagequant` requires an RGBA buffer, so we convert the input.
+        buffers.rgba.clear();
+        buffers.rgba.extend(rgb.pixels().map(|p| LiqRGBA {
+            r: p[0],
+            g: p[1],
+            b: p[2],
+            a: 255,
+        }));
+
+        let mut liq_img = LiqImage::new_borrowed(
+            &self.liq_attr,
+            &buffers.rgba,
+            w as usize,
+            h as usize,
+            0.0, // Treat as sRGB, as recommended by imagequant docs
+        )
+        .map_err(|e| {
+            error::AnsiImageError::Processing(format!("imagequant new_image failed: {e:?}"))
+        })?;
+
+        // Use the provided fixed palette instead of generating a new one.
+        let mut res =
+            LiqResult::from_palette(&self.liq_attr, &self.liq_palette, 0.0).map_err(|e| {
+                error::AnsiImageError::Processing(format!("imagequant from_palette failed: {e:?}"))
+            })?;
+
+        // Dithering strength: 1.0 = strongest, 0.0 = none.
+        let level = if self.settings.advanced.dithering.is_enabled {
+            1.0
+        } else {
+            0.0
+        };
+        res.set_dithering_level(level).map_err(|e| {
+            error::AnsiImageError::Processing(format!(
+                "imagequant set_dithering_level failed: {e:?}"
+            ))
+        })?;
+
+        // Remap the image to the palette and get the resulting pixel indices.
+        let out_palette = res
+            .remap_into_vec(&mut liq_img, &mut buffers.indices)
+            .map_err(|e| {
+                error::AnsiImageError::Processing(format!("imagequant remapped failed: {e:?}"))
+            })?;
+
+        // Expand the indexed pixels back into a full RGB image for the next processing stage.
+        let mut out_buffer = std::mem::take(&mut buffers.quantized);
+        out_buffer.clear();
+        for &idx in &buffers.indices {
+            let c = out_palette[idx as usize];
+            out_buffer.extend_from_slice(&[c.r, c.g, c.b]);
+        }
+
+        RgbImage::from_vec(w, h, out_buffer).ok_or_else(|| {
+            error::AnsiImageError::Processing(
+                "Failed to construct RgbImage from quantized buffer".into(),
+            )
+        })
+    }
+}
+
+/// Adds the padding, overlays and frame of `settings` to a converted grid.
+pub(crate) fn decorate(
+    canvas: Canvas,
+    (box_w, box_h): (usize, usize),
+    settings: &Settings,
+) -> Canvas {
+    // 8. Optionally pad the character grid to the full requested size.
+    let padding = &settings.size.padding;
+    let mut canvas = if padding.is_enabled {
+        canvas.padded(
+            box_w,
+            box_h,
+            padding.horizontal,
+            padding.vertical,
+            padding.fill,
+        )
+    } else {
+        canvas
+    };
+
+    // 9. Draw text overlays on top of the image.
+    for overlay in &settings.overlays {
+        canvas.overlay(overlay);
+    }
+
+    // 10. Optionally draw a frame around the result.
+    if settings.frame.is_enabled {
+        canvas.framed(&settings.frame)
+    } else {
+        canvas
+    }
+}
+
+/// Returns the 16-bit RGB image kept in `slot`, replacing it first if it does
+/// not have the given dimensions.
+fn reuse_image<'a>(
+    slot: &'a mut Option<Image<'static>>,
+    width: u32,
+    height: u32,
+) -> &'a mut Image<'static> {
+    if slot
+        .as_ref()
+        .is_some_and(|image| (image.width(), image.height()) != (width, height))
+    {
+        *slot = None;
+    }
+    slot.get_or_insert_with(|| Image::new(width, height, PixelType::U16x3))
+}
+
+/// Wraps a resizer error in a `Processing` error.
+fn processing_error(e: impl ToString) -> error::AnsiImageError {
+    error::AnsiImageError::Processing(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    use super::Converter;
+    use crate::settings::{Settings, Size};
+
+    #[test]
+    fn reused_converter_matches_fresh_conversions() {
+        let mut settings = Settings {
+            size: Size {
+                width: 6,
+                height: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        settings.colors.is_truecolor = false;
+        settings.colors.palette = vec![Rgb([0, 0, 0]), Rgb([200, 40, 40]), Rgb([255; 3])];
+        settings.advanced.linear_light = true;
+        settings.advanced.dithering.is_enabled = true;
+
+        // Frames of different sizes exercise both reused and replaced buffers, and
+        // a dedicated thread pool gives the same output as the global one.
+        let mut threaded = settings.clone();
+        threaded.advanced.threads = 2;
+        let mut converter = Converter::new(threaded).unwrap();
+        for (w, h) in [(24, 12), (24, 12), (40, 10), (24, 12)] {
+            let img = DynamicImage::ImageRgb8(RgbImage::from_fn(w, h, |x, y| {
+                Rgb([(x * 10) as u8, (y * 20) as u8, (x * y) as u8])
+            }));
+            let fresh = Converter::new(settings.clone())
+                .unwrap()
+                .convert_image(&img)
+                .unwrap();
+            assert_eq!(converter.convert_image(&img).unwrap(), fresh);
+        }
+
+        let mut invalid = Settings::default();
+        invalid.size.width = 0;
+        assert!(Converter::new(invalid).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Edge detection for drawing outlines with line characters.
+//!
+//! Edges are detected on the lightness of the resized image and reduced to one
+//! orientation per character cell. Cells on a strong enough edge are then drawn
+//! with a directional glyph (`|`, `_`, `/`, `\`) instead of a brightness-ramp
+//! character, which greatly improves the readability of ASCII art.
+
+use image::{Rgb, RgbImage};
+
+use crate::{
+    filters::gaussian_blur,
+    parallel::prelude::*,
+    processing::pixel_to_luv,
+    settings::{EdgeDetector, Edges},
+};
+
+/// The standard deviation of the narrower blur in difference-of-Gaussians mode.
+const DOG_SIGMA: f32 = 1.0;
+/// The ratio between the wider and narrower blur in difference-of-Gaussians mode.
+const DOG_K: f32 = 1.6;
+
+/// A per-cell map of edge glyphs for a character grid.
+#[derive(Debug, Clone)]
+pub struct EdgeMap {
+    width: usize,
+    glyphs: Vec<Option<char>>,
+}
+
+impl EdgeMap {
+    /// Detects edges in an image that has been resized to 2x the character grid.
+    ///
+    /// Each character cell covers a 2x2 pixel block. Cells whose average edge
+    /// strength exceeds [`Edges::threshold`] get a glyph from [`Edges::style`]
+    /// matching the dominant edge orientation.
+    #[must_use]
+    pub fn detect(img: &RgbImage, width_char: usize, height_char: usize, edges: &Edges) -> Self {
+        let (w, h) = (img.width() as usize, img.height() as usize);
+
+        let mut lightness: Vec<f32> = img
+            .as_raw()
+            .par_chunks_exact(3)
+            .map(|p| pixel_to_luv(Rgb([p[0], p[1], p[2]])).l / 100.0)
+            .collect();
+
+        if edges.detector == EdgeDetector::DifferenceOfGaussians {
+            let narrow = gaussian_blur(&lightness, w, h, DOG_SIGMA);
+            let wide = gaussian_blur(&lightness, w, h, DOG_SIGMA * DOG_K);
+            lightness = narrow.iter().zip(&wide).map(|(a, b)| a - b).collect();
+
+            // Normalize the response so the threshold behaves like it does for Sobel.
+            let peak = lightness.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+            if peak > f32::EPSILON {
+                for v in &mut lightness {
+                    *v /= peak;
+                }
+            }
+        }
+
+        let [vertical, horizontal, rising, falling] = edges.style.glyphs();
+        let mut glyphs = vec![None; width_char * height_char];
+        if width_char > 0 {
+            glyphs
+                .par_chunks_mut(width_char)
+                .enumerate()
+                .for_each(|(y_char, row)| {
+                    for (x_char, glyph) in row.iter_mut().enumerate() {
+                        // Accumulate the gradient magnitude and the structure tensor
+                        // (in doubled-angle form, so opposite gradients reinforce).
+                        let (mut magnitude, mut jxx, mut jxy) = (0.0, 0.0, 0.0);
+                        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                            let (gx, gy) =
+                                sobel(&lightness, w, h, x_char * 2 + dx, y_char * 2 + dy);
+                            magnitude += gx.hypot(gy);
+                            jxx += gx.mul_add(gx, -gy * gy);
+                            jxy += 2.0 * gx * gy;
+                        }
+                        if magnitude / 4.0 < edges.threshold {
+                            continue;
+                        }
+
+                        // The gradient angle in degrees, within (-90, 90]. Image y
+                        // points down, so a positive angle means "down and right".
+                        let angle = (0.5 * f32::atan2(jxy, jxx)).to_degrees();
+                        *glyph = Some(if angle.abs() < 22.5 {
+                            vertical
+                        } else if angle.abs() > 67.5 {
+                            horizontal
+                        } else if angle > 0.0 {
+                            rising
+                        } else {
+                            falling
+                        });
+                    }
+                });
+        }
+
+        Self {
+            width: width_char,
+            glyphs,
+        }
+    }
+
+    /// Returns the edge glyph for a character cell, if it lies on an edge.
+    #[must_use]
+    pub fn glyph(&self, x_char: usize, y_char: usize) -> Option<char> {
+        self.glyphs
+            .get(y_char * self.width + x_char)
+            .copied()
+            .flatten()
+    }
+}
+
+/// Computes the normalized Sobel gradient `(gx, gy)` at a pixel.
+///
+/// Samples beyond the image bounds are clamped to the nearest edge pixel. For a
+/// plane in `0.0..=1.0`, each component lies in `-1.0..=1.0`.
+fn sobel(plane: &[f32], w: usize, h: usize, x: usize, y: usize) -> (f32, f32) {
+    let at = |dx: isize, dy: isize| {
+        let sx = x.saturating_add_signed(dx).min(w - 1);
+        let sy = y.saturating_add_signed(dy).min(h - 1);
+        plane[sy * w + sx]
+    };
+    let gx = (at(1, -1) + 2.0 * at(1, 0) + at(1, 1)) - (at(-1, -1) + 2.0 * at(-1, 0) + at(-1, 1));
+    let gy = (at(-1, 1) + 2.0 * at(0, 1) + at(1, 1)) - (at(-1, -1) + 2.0 * at(0, -1) + at(1, -1));
+    (gx / 4.0, gy / 4.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::EdgeMap;
+    use crate::settings::Edges;
+
+    #[test]
+    fn edge_orientation() {
+        let edges = Edges {
+            is_enabled: true,
+            ..Default::default()
+        };
+
+        // A vertical boundary between black and white columns.
+        let img = RgbImage::from_fn(8, 8, |x, _| if x < 4 { Rgb([0; 3]) } else { Rgb([255; 3]) });
+        let map = EdgeMap::detect(&img, 4, 4, &edges);
+        assert_eq!(map.glyph(1, 1), Some('|'));
+        assert_eq!(map.glyph(0, 1), None);
+
+        // A horizontal boundary between black and white rows.
+        let img = RgbImage::from_fn(8, 8, |_, y| if y < 4 { Rgb([0; 3]) } else { Rgb([255; 3]) });
+        let map = EdgeMap::detect(&img, 4, 4, &edges);
+        assert_eq!(map.glyph(1, 1), Some('_'));
+
+        // A diagonal boundary rising from bottom-left to top-right.
+        let img = RgbImage::from_fn(8, 8, |x, y| {
+            if x + y < 8 {
+                Rgb([0; 3])
+            } else {
+                Rgb([255; 3])
+            }
+        });
+        let map = EdgeMap::detect(&img, 4, 4, &edges);
+        assert_eq!(map.glyph(2, 1), Some('/'));
+    }
+}
@@ -33,4 +33,10 @@ pub enum AnsiImageError {
     /// resizing, color quantization, or buffer manipulation.
     #[error("Image processing failed: {0}")]
     Processing(String),
+
+    /// The conversion was cancelled through a
+    /// [`CancellationToken`](crate::progress::CancellationToken) before it
+    /// finished.
+    #[error("Conversion was cancelled.")]
+    Cancelled,
 }
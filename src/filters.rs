@@ -0,0 +1,447 @@
+//! Image-space filters applied to the resized image before character selection.
+//!
+//! These operate on the small, already-resized RGB buffer, so their cost scales
+//! with the output size rather than the size of the source image.
+
+use image::{Rgb, RgbImage};
+
+use crate::{
+    parallel::prelude::*,
+    processing::{LuvColor, luv_to_rgb, pixel_to_luv},
+    settings::{Adjustments, Equalization, Sharpen},
+};
+
+/// The number of histogram bins used for lightness equalization.
+const HIST_BINS: usize = 256;
+
+/// A row-major 3x3 matrix applied to linear combinations of RGB channels.
+type ColorMatrix = [[f32; 3]; 3];
+
+/// The identity color matrix.
+const IDENTITY: ColorMatrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Applies the tonal and color [`Adjustments`] to an image in place.
+///
+/// Per-channel operations (invert, brightness, contrast, gamma) are folded into a
+/// 256-entry lookup table, while cross-channel operations (saturation, hue
+/// rotation, grayscale) are folded into a single color matrix.
+pub fn apply_adjustments(img: &mut RgbImage, adjustments: &Adjustments) {
+    if adjustments.is_identity() {
+        return;
+    }
+
+    let lut = channel_lut(adjustments);
+    let matrix = color_matrix(adjustments);
+    let apply_matrix = matrix != IDENTITY;
+
+    let row_len = img.width() as usize * 3;
+    if row_len == 0 {
+        return;
+    }
+    img.par_chunks_mut(row_len).for_each(|row| {
+        for px in row.chunks_exact_mut(3) {
+            let (r, g, b) = (
+                lut[px[0] as usize],
+                lut[px[1] as usize],
+                lut[px[2] as usize],
+            );
+            let out = if apply_matrix {
+                [
+                    matrix[0][2].mul_add(b, matrix[0][0].mul_add(r, matrix[0][1] * g)),
+                    matrix[1][2].mul_add(b, matrix[1][0].mul_add(r, matrix[1][1] * g)),
+                    matrix[2][2].mul_add(b, matrix[2][0].mul_add(r, matrix[2][1] * g)),
+                ]
+            } else {
+                [r, g, b]
+            };
+            for (dst, v) in px.iter_mut().zip(out) {
+                *dst = to_u8(v);
+            }
+        }
+    });
+}
+
+/// Builds a lookup table mapping each 8-bit channel value to its adjusted value in `0.0..=1.0`.
+fn channel_lut(adjustments: &Adjustments) -> [f32; 256] {
+    let inv_gamma = 1.0 / adjustments.gamma.max(f32::EPSILON);
+    let mut lut = [0.0; 256];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let mut v = i as f32 / 255.0;
+        if adjustments.invert {
+            v = 1.0 - v;
+        }
+        v += adjustments.brightness;
+        v = (v - 0.5).mul_add(adjustments.contrast, 0.5).clamp(0.0, 1.0);
+        *entry = v.powf(inv_gamma);
+    }
+    lut
+}
+
+/// Builds the combined saturation, hue rotation, and grayscale matrix.
+///
+/// The coefficients follow the W3C Filter Effects `saturate` and `hue-rotate`
+/// definitions, which use Rec. 709 luma weights.
+fn color_matrix(adjustments: &Adjustments) -> ColorMatrix {
+    let mut matrix = saturation_matrix(adjustments.saturation);
+    if adjustments.hue_rotation != 0.0 {
+        matrix = multiply(&hue_rotation_matrix(adjustments.hue_rotation), &matrix);
+    }
+    if adjustments.grayscale {
+        matrix = multiply(&saturation_matrix(0.0), &matrix);
+    }
+    matrix
+}
+
+/// Returns the matrix scaling saturation by `s`.
+fn saturation_matrix(s: f32) -> ColorMatrix {
+    if (s - 1.0).abs() < f32::EPSILON {
+        return IDENTITY;
+    }
+    [
+        [
+            0.787f32.mul_add(s, 0.213),
+            0.715 - 0.715 * s,
+            0.072 - 0.072 * s,
+        ],
+        [
+            0.213 - 0.213 * s,
+            0.285f32.mul_add(s, 0.715),
+            0.072 - 0.072 * s,
+        ],
+        [
+            0.213 - 0.213 * s,
+            0.715 - 0.715 * s,
+            0.928f32.mul_add(s, 0.072),
+        ],
+    ]
+}
+
+/// Returns the matrix rotating hue by `degrees`.
+fn hue_rotation_matrix(degrees: f32) -> ColorMatrix {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [
+        [
+            0.213 + cos * 0.787 - sin * 0.213,
+            0.715 - cos * 0.715 - sin * 0.715,
+            0.072 - cos * 0.072 + sin * 0.928,
+        ],
+        [
+            0.213 - cos * 0.213 + sin * 0.143,
+            0.715 + cos * 0.285 + sin * 0.140,
+            0.072 - cos * 0.072 - sin * 0.283,
+        ],
+        [
+            0.213 - cos * 0.213 - sin * 0.787,
+            0.715 - cos * 0.715 + sin * 0.715,
+            0.072 + cos * 0.928 + sin * 0.072,
+        ],
+    ]
+}
+
+/// Multiplies two color matrices, yielding `a * b` (`b` is applied first).
+fn multiply(a: &ColorMatrix, b: &ColorMatrix) -> ColorMatrix {
+    let mut out = [[0.0; 3]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    out
+}
+
+/// Equalizes the L* (lightness) histogram of an image in place.
+///
+/// Only lightness is remapped; each pixel's chromaticity is preserved by scaling
+/// its u* and v* components along with L*.
+pub fn equalize(img: &mut RgbImage, equalization: Equalization) {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    if equalization == Equalization::None || w == 0 || h == 0 {
+        return;
+    }
+
+    let luv: Vec<LuvColor> = img
+        .as_raw()
+        .par_chunks_exact(3)
+        .map(|p| pixel_to_luv(Rgb([p[0], p[1], p[2]])))
+        .collect();
+
+    let new_lightness: Vec<f32> = match equalization {
+        Equalization::None => return,
+        Equalization::Global => {
+            let mut hist = [0.0; HIST_BINS];
+            for c in &luv {
+                hist[lightness_bin(c.l)] += 1.0;
+            }
+            let lut = equalization_lut(hist, None);
+            luv.iter().map(|c| lut[lightness_bin(c.l)]).collect()
+        }
+        Equalization::Clahe { clip_limit, tiles } => clahe(&luv, w, h, clip_limit, tiles),
+    };
+
+    img.par_chunks_mut(w * 3).enumerate().for_each(|(y, row)| {
+        for (x, px) in row.chunks_exact_mut(3).enumerate() {
+            let i = y * w + x;
+            let c = luv[i];
+            let ratio = if c.l > 1e-3 {
+                new_lightness[i] / c.l
+            } else {
+                1.0
+            };
+            let l = if c.l > 1e-3 { new_lightness[i] } else { c.l };
+            let (r, g, b) = luv_to_rgb(LuvColor::new(l, c.u * ratio, c.v * ratio));
+            px.copy_from_slice(&[r, g, b]);
+        }
+    });
+}
+
+/// Computes the equalized lightness of every pixel using CLAHE.
+///
+/// Each tile gets its own clipped equalization table, and every pixel bilinearly
+/// blends the tables of the four nearest tile centers to avoid visible seams.
+fn clahe(luv: &[LuvColor], w: usize, h: usize, clip_limit: f32, tiles: u32) -> Vec<f32> {
+    let tiles_x = (tiles as usize).clamp(1, w);
+    let tiles_y = (tiles as usize).clamp(1, h);
+
+    // Build one lookup table per tile.
+    let luts: Vec<[f32; HIST_BINS]> = (0..tiles_x * tiles_y)
+        .map(|t| {
+            let (tx, ty) = (t % tiles_x, t / tiles_x);
+            let (x0, x1) = (tx * w / tiles_x, (tx + 1) * w / tiles_x);
+            let (y0, y1) = (ty * h / tiles_y, (ty + 1) * h / tiles_y);
+            let mut hist = [0.0; HIST_BINS];
+            for y in y0..y1 {
+                for c in &luv[y * w + x0..y * w + x1] {
+                    hist[lightness_bin(c.l)] += 1.0;
+                }
+            }
+            equalization_lut(hist, Some(clip_limit))
+        })
+        .collect();
+
+    let tile_w = w as f32 / tiles_x as f32;
+    let tile_h = h as f32 / tiles_y as f32;
+
+    // Returns the two neighboring tile indices and the blend weight of the second.
+    let neighbors = |pos: usize, tile_size: f32, count: usize| {
+        let g = ((pos as f32 + 0.5) / tile_size - 0.5).max(0.0);
+        let t0 = (g.floor() as usize).min(count - 1);
+        let t1 = (t0 + 1).min(count - 1);
+        (t0, t1, (g - t0 as f32).clamp(0.0, 1.0))
+    };
+
+    luv.par_iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let (x, y) = (i % w, i / w);
+            let (tx0, tx1, fx) = neighbors(x, tile_w, tiles_x);
+            let (ty0, ty1, fy) = neighbors(y, tile_h, tiles_y);
+            let bin = lightness_bin(c.l);
+            let top = lerp(
+                luts[ty0 * tiles_x + tx0][bin],
+                luts[ty0 * tiles_x + tx1][bin],
+                fx,
+            );
+            let bottom = lerp(
+                luts[ty1 * tiles_x + tx0][bin],
+                luts[ty1 * tiles_x + tx1][bin],
+                fx,
+            );
+            lerp(top, bottom, fy)
+        })
+        .collect()
+}
+
+/// Builds a table mapping each lightness bin to its equalized L* value (`0.0..=100.0`).
+///
+/// If `clip_limit` is set, bins taller than `clip_limit` times the average bin
+/// height are clipped and the excess is redistributed evenly across all bins.
+fn equalization_lut(mut hist: [f32; HIST_BINS], clip_limit: Option<f32>) -> [f32; HIST_BINS] {
+    let total: f32 = hist.iter().sum();
+
+    if let Some(clip_limit) = clip_limit {
+        let limit = (clip_limit * total / HIST_BINS as f32).max(1.0);
+        let excess: f32 = hist.iter().map(|&n| (n - limit).max(0.0)).sum();
+        let bonus = excess / HIST_BINS as f32;
+        for n in &mut hist {
+            *n = n.min(limit) + bonus;
+        }
+    }
+
+    let mut lut = [0.0; HIST_BINS];
+    let cdf_min = hist.iter().copied().find(|&n| n > 0.0).unwrap_or(0.0);
+    let range = total - cdf_min;
+    let mut cdf = 0.0;
+    for (i, (entry, n)) in lut.iter_mut().zip(hist).enumerate() {
+        cdf += n;
+        *entry = if range > f32::EPSILON {
+            ((cdf - cdf_min) / range).clamp(0.0, 1.0) * 100.0
+        } else {
+            // A flat image has nothing to equalize, so keep its lightness.
+            i as f32 / (HIST_BINS - 1) as f32 * 100.0
+        };
+    }
+    lut
+}
+
+/// Sharpens an image in place using an unsharp mask.
+///
+/// Each channel is blurred with a Gaussian of standard deviation
+/// [`Sharpen::radius`], and the difference between the original and the blurred
+/// copy, scaled by [`Sharpen::amount`], is added back to the original.
+pub fn sharpen(img: &mut RgbImage, sharpen: &Sharpen) {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    if w == 0 || h == 0 || sharpen.amount == 0.0 {
+        return;
+    }
+
+    let blurred: Vec<Vec<f32>> = (0..3)
+        .map(|c| {
+            let plane: Vec<f32> = img
+                .as_raw()
+                .iter()
+                .skip(c)
+                .step_by(3)
+                .map(|&v| f32::from(v))
+                .collect();
+            gaussian_blur(&plane, w, h, sharpen.radius)
+        })
+        .collect();
+
+    img.par_chunks_mut(w * 3).enumerate().for_each(|(y, row)| {
+        for (x, px) in row.chunks_exact_mut(3).enumerate() {
+            for (c, v) in px.iter_mut().enumerate() {
+                let orig = f32::from(*v);
+                let detail = orig - blurred[c][y * w + x];
+                *v = detail
+                    .mul_add(sharpen.amount, orig)
+                    .round()
+                    .clamp(0.0, 255.0) as u8;
+            }
+        }
+    });
+}
+
+/// Blurs a single-channel plane with a separable Gaussian kernel.
+///
+/// Samples beyond the image bounds are clamped to the nearest edge pixel.
+pub(crate) fn gaussian_blur(plane: &[f32], w: usize, h: usize, sigma: f32) -> Vec<f32> {
+    if w == 0 || h == 0 || sigma <= 0.0 {
+        return plane.to_vec();
+    }
+    let kernel = gaussian_kernel(sigma);
+    let radius = kernel.len() / 2;
+
+    // Horizontal pass.
+    let mut tmp = vec![0.0; plane.len()];
+    tmp.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        let src = &plane[y * w..(y + 1) * w];
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| src[(x + k).saturating_sub(radius).min(w - 1)] * weight)
+                .sum();
+        }
+    });
+
+    // Vertical pass.
+    let mut out = vec![0.0; plane.len()];
+    out.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| tmp[(y + k).saturating_sub(radius).min(h - 1) * w + x] * weight)
+                .sum();
+        }
+    });
+    out
+}
+
+/// Builds a normalized 1D Gaussian kernel covering three standard deviations.
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let radius = (sigma * 3.0).ceil() as i32;
+    let denom = 2.0 * sigma * sigma;
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / denom).exp())
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    for k in &mut kernel {
+        *k /= sum;
+    }
+    kernel
+}
+
+/// Maps an L* value (`0.0..=100.0`) to its histogram bin.
+#[inline]
+fn lightness_bin(l: f32) -> usize {
+    ((l / 100.0 * (HIST_BINS - 1) as f32).round() as usize).min(HIST_BINS - 1)
+}
+
+/// Linearly interpolates from `a` to `b` by `t`.
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    (b - a).mul_add(t, a)
+}
+
+/// Converts a normalized channel value back to an 8-bit value.
+#[inline]
+fn to_u8(v: f32) -> u8 {
+    (v * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::{apply_adjustments, equalize, sharpen};
+    use crate::settings::{Adjustments, Equalization, Sharpen};
+
+    #[test]
+    fn adjustments_invert_and_grayscale() {
+        let mut img = RgbImage::from_pixel(2, 2, Rgb([255, 0, 0]));
+        let adjustments = Adjustments {
+            invert: true,
+            ..Default::default()
+        };
+        apply_adjustments(&mut img, &adjustments);
+        assert_eq!(*img.get_pixel(1, 1), Rgb([0, 255, 255]));
+
+        let adjustments = Adjustments {
+            grayscale: true,
+            ..Default::default()
+        };
+        apply_adjustments(&mut img, &adjustments);
+        let Rgb([r, g, b]) = *img.get_pixel(0, 0);
+        assert!(r == g && g == b);
+    }
+
+    #[test]
+    fn equalization_stretches_low_contrast() {
+        let mut img = RgbImage::from_fn(16, 16, |x, _| {
+            let v = 100 + (x as u8 % 8);
+            Rgb([v, v, v])
+        });
+        equalize(&mut img, Equalization::Global);
+        let min = img.pixels().map(|p| p[0]).min().unwrap();
+        let max = img.pixels().map(|p| p[0]).max().unwrap();
+        assert!(min < 10 && max > 245, "range {min}..{max}");
+    }
+
+    #[test]
+    fn sharpen_increases_edge_contrast() {
+        let mut img = RgbImage::from_fn(
+            8,
+            1,
+            |x, _| if x < 4 { Rgb([64; 3]) } else { Rgb([192; 3]) },
+        );
+        let settings = Sharpen {
+            is_enabled: true,
+            ..Default::default()
+        };
+        sharpen(&mut img, &settings);
+        assert!(img.get_pixel(3, 0)[0] < 64);
+        assert!(img.get_pixel(4, 0)[0] > 192);
+        assert_eq!(img.get_pixel(0, 0)[0], 64);
+    }
+}
@@ -0,0 +1,222 @@
+//! Geometry helpers for orienting the source image and selecting the region to render.
+//!
+//! The functions here apply the rotation and flips of the [`Transform`] settings,
+//! and turn its crop and zoom into a concrete rectangle in source pixel
+//! coordinates, which is then handed to the resizer as a crop box.
+
+use std::borrow::Cow;
+
+use image::DynamicImage;
+
+use crate::{
+    error,
+    settings::{Crop, Rotation, Transform},
+};
+
+/// A rectangle in source image pixel coordinates.
+///
+/// Coordinates are fractional so that zooming and panning stay smooth; the
+/// resizer samples sub-pixel regions directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    /// The left edge of the rectangle.
+    pub x: f64,
+    /// The top edge of the rectangle.
+    pub y: f64,
+    /// The width of the rectangle.
+    pub width: f64,
+    /// The height of the rectangle.
+    pub height: f64,
+}
+
+impl Rect {
+    /// Returns the rectangle's width and height rounded to whole pixels, and at
+    /// least one pixel each.
+    #[must_use]
+    pub fn dimensions(&self) -> (u32, u32) {
+        (
+            (self.width.round() as u32).max(1),
+            (self.height.round() as u32).max(1),
+        )
+    }
+
+    /// Returns the largest centered sub-rectangle with the given width-to-height
+    /// aspect ratio.
+    #[must_use]
+    pub fn crop_to_aspect(&self, aspect: f64) -> Self {
+        let (width, height) = if self.width / self.height > aspect {
+            (self.height * aspect, self.height)
+        } else {
+            (self.width, self.width / aspect)
+        };
+        Self {
+            x: self.x + (self.width - width) / 2.0,
+            y: self.y + (self.height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+}
+
+/// Applies the rotation and flips of a [`Transform`] to an image.
+///
+/// The image is only copied if at least one transform is active.
+#[must_use]
+pub fn orient<'a>(img: &'a DynamicImage, transform: &Transform) -> Cow<'a, DynamicImage> {
+    let mut img = match transform.rotation {
+        Rotation::None => Cow::Borrowed(img),
+        Rotation::Rotate90 => Cow::Owned(img.rotate90()),
+        Rotation::Rotate180 => Cow::Owned(img.rotate180()),
+        Rotation::Rotate270 => Cow::Owned(img.rotate270()),
+    };
+    if transform.flip_horizontal {
+        img.to_mut()
+            .apply_orientation(image::metadata::Orientation::FlipHorizontal);
+    }
+    if transform.flip_vertical {
+        img.to_mut()
+            .apply_orientation(image::metadata::Orientation::FlipVertical);
+    }
+    img
+}
+
+/// Computes the viewport of a source image selected by a [`Transform`].
+///
+/// The crop region is clipped to the image bounds, and the zoom and pan are then
+/// applied within that region.
+///
+/// # Errors
+///
+/// Returns an `InvalidSettings` error if the crop region does not overlap the image.
+pub fn viewport(img_w: u32, img_h: u32, transform: &Transform) -> error::Result<Rect> {
+    let (img_w, img_h) = (f64::from(img_w), f64::from(img_h));
+
+    let (x, y, width, height) = match transform.crop {
+        Crop::None => (0.0, 0.0, img_w, img_h),
+        Crop::Pixels {
+            x,
+            y,
+            width,
+            height,
+        } => (
+            f64::from(x),
+            f64::from(y),
+            f64::from(width),
+            f64::from(height),
+        ),
+        Crop::Normalized {
+            x,
+            y,
+            width,
+            height,
+        } => (
+            f64::from(x) * img_w,
+            f64::from(y) * img_h,
+            f64::from(width) * img_w,
+            f64::from(height) * img_h,
+        ),
+    };
+
+    // Clip the region to the image bounds.
+    let (left, right) = (x.clamp(0.0, img_w), (x + width).clamp(0.0, img_w));
+    let (top, bottom) = (y.clamp(0.0, img_h), (y + height).clamp(0.0, img_h));
+    if right - left < 1.0 || bottom - top < 1.0 {
+        return Err(error::AnsiImageError::InvalidSettings(
+            "The crop region does not overlap the image.".into(),
+        ));
+    }
+    let region = Rect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    };
+
+    // Shrink the region by the zoom factor around the panned center.
+    let zoom = f64::from(transform.zoom.max(1.0));
+    let (view_w, view_h) = (region.width / zoom, region.height / zoom);
+    let pan_x = f64::from(transform.pan_x.clamp(-1.0, 1.0));
+    let pan_y = f64::from(transform.pan_y.clamp(-1.0, 1.0));
+    let center_x = (region.width - view_w).mul_add(pan_x * 0.5, region.x + region.width / 2.0);
+    let center_y = (region.height - view_h).mul_add(pan_y * 0.5, region.y + region.height / 2.0);
+
+    Ok(Rect {
+        x: center_x - view_w / 2.0,
+        y: center_y - view_h / 2.0,
+        width: view_w,
+        height: view_h,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImageView as _, Rgb, RgbImage};
+
+    use super::{Rect, orient, viewport};
+    use crate::settings::{Crop, Rotation, Transform};
+
+    #[test]
+    fn orient_rotates_then_flips() {
+        let mut img = RgbImage::new(3, 2);
+        img.put_pixel(0, 0, Rgb([255, 0, 0]));
+        let img = DynamicImage::ImageRgb8(img);
+
+        let transform = Transform {
+            rotation: Rotation::Rotate90,
+            flip_vertical: true,
+            ..Default::default()
+        };
+        let oriented = orient(&img, &transform);
+        assert_eq!(oriented.dimensions(), (2, 3));
+        // The top-left pixel moves to the top-right, then to the bottom-right.
+        assert_eq!(oriented.get_pixel(1, 2).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn viewport_crop_and_zoom() {
+        let transform = Transform {
+            crop: Crop::Pixels {
+                x: 100,
+                y: 0,
+                width: 1000,
+                height: 50,
+            },
+            ..Default::default()
+        };
+        // The crop is clipped to the 200x100 image.
+        let rect = viewport(200, 100, &transform).unwrap();
+        assert_eq!(
+            rect,
+            Rect {
+                x: 100.0,
+                y: 0.0,
+                width: 100.0,
+                height: 50.0
+            }
+        );
+
+        // Zooming 2x into the bottom-right corner of the whole image.
+        let transform = Transform::default().zoom_at(2.0, 1.0, 1.0);
+        let rect = viewport(200, 100, &transform).unwrap();
+        assert_eq!(
+            rect,
+            Rect {
+                x: 100.0,
+                y: 50.0,
+                width: 100.0,
+                height: 50.0
+            }
+        );
+
+        let transform = Transform {
+            crop: Crop::Normalized {
+                x: 1.5,
+                y: 0.0,
+                width: 0.5,
+                height: 1.0,
+            },
+            ..Default::default()
+        };
+        assert!(viewport(200, 100, &transform).is_err());
+    }
+}
@@ -27,28 +27,55 @@
 #![allow(clippy::cast_precision_loss)]
 #![allow(clippy::similar_names)]
 
+pub mod auto;
+pub mod canvas;
+// The C API exchanges raw pointers with the caller.
+#[cfg(feature = "capi")]
+#[allow(unsafe_code)]
+pub mod capi;
+pub mod compare;
+pub mod converter;
+pub mod edges;
 pub mod error;
+pub mod filters;
+pub mod geometry;
+mod luv;
+pub mod metrics;
+pub mod montage;
+pub mod nearest;
 pub mod palettes;
+mod parallel;
 pub mod processing;
+pub mod progress;
+mod serialization;
 pub mod sets;
 pub mod settings;
+// `wasm_bindgen` exports are implemented with generated `unsafe` glue code.
+#[cfg(feature = "wasm")]
+#[allow(unsafe_code)]
+pub mod wasm;
+
+use std::{
+    io::{BufRead, Cursor, Seek},
+    path::Path,
+};
 
-use std::path::Path;
+use image::{DynamicImage, ImageDecoder as _, metadata::Orientation};
 
-use fast_image_resize::images::Image;
-use fast_image_resize::{PixelType, ResizeOptions, Resizer};
-use image::{DynamicImage, GenericImageView};
-use imagequant::{
-    Attributes as LiqAttr, Image as LiqImage, QuantizationResult as LiqResult, RGBA as LiqRGBA,
-};
-use rayon::iter::{
-    IndexedParallelIterator as _, IntoParallelRefMutIterator as _, ParallelIterator as _,
-};
+use self::converter::Grid;
 
 // Re-export key types for consumers of the library.
+pub use self::auto::{convert_auto, convert_image_auto};
+pub use self::canvas::{Canvas, Cell};
+pub use self::compare::{compare, compare_image};
+pub use self::converter::Converter;
+pub use self::montage::convert_montage;
+pub use self::progress::CancellationToken;
 pub use self::settings::{
-    Advanced, AsciiCharSet, CharacterMode, Characters, ColorMode, Colors, DitherMatrix, Dithering,
-    Settings, Size, SizeMode, UnicodeCharSet,
+    Adjustments, Advanced, Alignment, AsciiCharSet, CharacterMode, Characters, ColorMode, Colors,
+    Crop, DitherMatrix, Dithering, EdgeDetector, EdgeStyle, Edges, Equalization, Frame, FrameStyle,
+    Montage, Padding, PaddingFill, Rotation, Settings, SettingsBuilder, Sharpen, Size, SizeMode,
+    Transform, UnicodeCharSet,
 };
 
 /// The black color constant in the L*u*v* color space, used for brightness calculations.
@@ -57,7 +84,7 @@ pub(crate) const BLACK_LUV: processing::LuvColor = palette::Luv::new(0.0, 0.0, 0
 /// Converts an image file into a styled terminal string based on the provided settings.
 ///
 /// This is the main entry point for the library. It handles image loading, decoding,
-/// resizing, optional dithering, and the core conversion logic.
+/// EXIF orientation, resizing, optional dithering, and the core conversion logic.
 ///
 /// # Arguments
 ///
@@ -76,14 +103,28 @@ pub(crate) const BLACK_LUV: processing::LuvColor = palette::Luv::new(0.0, 0.0, 0
 /// * The image format is unsupported or the data is corrupt.
 /// * The provided settings are invalid (e.g., an empty custom character set or color palette).
 pub fn convert(path: &Path, settings: &Settings) -> error::Result<String> {
-    let img = image::open(path)?;
+    let img = load_image(path, settings.transform.auto_orient)?;
     convert_image(&img, settings)
 }
 
+/// Converts an image file into a [`Canvas`] of styled cells.
+///
+/// This is the cell-level counterpart of [`convert`], for callers that want to
+/// edit or composite the result before rendering it with [`Canvas::render`].
+///
+/// # Errors
+///
+/// This function can fail for the same reasons as [`convert`].
+pub fn convert_to_canvas(path: &Path, settings: &Settings) -> error::Result<Canvas> {
+    let img = load_image(path, settings.transform.auto_orient)?;
+    convert_image_to_canvas(&img, settings)
+}
+
 /// Converts a pre-loaded [`DynamicImage`] into a styled terminal string.
 ///
 /// This function is an alternative to [`convert`] for cases where the image is
-/// already in memory.
+/// already in memory. Since the image is already decoded, its EXIF orientation is
+/// not applied; use [`DynamicImage::apply_orientation`] beforehand if needed.
 ///
 /// # Arguments
 ///
@@ -99,88 +140,120 @@ pub fn convert(path: &Path, settings: &Settings) -> error::Result<String> {
 ///
 /// This function can fail if the provided settings are invalid.
 pub fn convert_image(img: &DynamicImage, settings: &Settings) -> error::Result<String> {
-    // 1. Validate settings before performing any expensive operations.
-    if !settings.colors.is_truecolor && settings.colors.palette.is_empty() {
-        return Err(error::AnsiImageError::InvalidSettings(
-            "A color palette must be selected when not in truecolor mode.".into(),
-        ));
-    }
-    if let CharacterMode::Custom(chars) = &settings.characters.mode
-        && chars.is_empty()
-    {
-        return Err(error::AnsiImageError::InvalidSettings(
-            "Custom character mode requires at least one character.".into(),
-        ));
-    }
-
-    // 2. Calculate final output dimensions in characters (width, height).
-    // The image is resized to 2x this size to sample 2x2 pixel blocks for each character.
-    let (img_w, img_h) = img.dimensions();
-    let (w, h) = calculate_dimensions(
-        img_w,
-        img_h,
-        settings.size.width,
-        settings.size.height,
-        settings.size.mode,
-        settings.characters.aspect_ratio,
-    );
-    let target_w = (w * 2) as u32;
-    let target_h = (h * 2) as u32;
-
-    // 3. Resize the image using a high-performance resizer.
-    let src_image = Image::from_vec_u8(img_w, img_h, img.to_rgb8().into_raw(), PixelType::U8x3)
-        .map_err(|e| error::AnsiImageError::Processing(e.to_string()))?;
+    let canvas = convert_image_to_canvas(img, settings)?;
+    Ok(canvas.render(settings.advanced.compression))
+}
 
-    let mut dst_image = Image::new(target_w, target_h, src_image.pixel_type());
+/// Converts a pre-loaded [`DynamicImage`] into a [`Canvas`] of styled cells.
+///
+/// This is the cell-level counterpart of [`convert_image`], for callers that want
+/// to edit or composite the result before rendering it with [`Canvas::render`].
+///
+/// # Errors
+///
+/// This function can fail if the provided settings are invalid.
+pub fn convert_image_to_canvas(img: &DynamicImage, settings: &Settings) -> error::Result<Canvas> {
+    Converter::new(settings.clone())?.convert_image_to_canvas(img)
+}
 
-    let algorithm = fast_image_resize::ResizeAlg::Convolution(settings.advanced.resize_filter);
-    let resize_options = ResizeOptions::new().resize_alg(algorithm);
+/// Runs the conversion pipeline up to the character grid.
+///
+/// # Errors
+///
+/// This function can fail if the provided settings are invalid.
+pub(crate) fn convert_image_to_grid(
+    img: &DynamicImage,
+    settings: &Settings,
+) -> error::Result<Grid> {
+    Converter::new(settings.clone())?.convert_image_to_grid(img)
+}
 
-    let mut resizer = Resizer::new();
-    resizer
-        .resize(&src_image, &mut dst_image, Some(&resize_options))
-        .map_err(|e| error::AnsiImageError::Processing(e.to_string()))?;
+/// Decodes an in-memory image file into a styled terminal string.
+///
+/// The format is detected from the data, so any supported format (PNG, JPEG,
+/// GIF, WebP, ...) can be passed as read from a file or received over the
+/// network. This is the entry point for environments without a file system,
+/// such as WebAssembly. The EXIF orientation is applied like in [`convert`].
+///
+/// # Errors
+///
+/// This function can fail if the data cannot be decoded, or for the same
+/// reasons as [`convert_image`].
+pub fn convert_bytes(bytes: &[u8], settings: &Settings) -> error::Result<String> {
+    let canvas = convert_bytes_to_canvas(bytes, settings)?;
+    Ok(canvas.render(settings.advanced.compression))
+}
 
-    let resized_buffer = image::RgbImage::from_raw(target_w, target_h, dst_image.into_vec())
-        .ok_or_else(|| {
-            error::AnsiImageError::Processing("Failed to create image from resized buffer.".into())
-        })?;
+/// Decodes an in-memory image file into a [`Canvas`] of styled cells.
+///
+/// This is the cell-level counterpart of [`convert_bytes`].
+///
+/// # Errors
+///
+/// This function can fail for the same reasons as [`convert_bytes`].
+pub fn convert_bytes_to_canvas(bytes: &[u8], settings: &Settings) -> error::Result<Canvas> {
+    let reader = image::ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    let (mut img, orientation) = decode(reader)?;
+    if settings.transform.auto_orient {
+        img.apply_orientation(orientation);
+    }
+    convert_image_to_canvas(&img, settings)
+}
 
-    // 4. Optionally apply color quantization and dithering if not in truecolor mode.
-    let processed_img = if settings.colors.is_truecolor {
-        resized_buffer
-    } else {
-        quantize_with_imagequant(
-            &resized_buffer,
-            &settings.colors.palette,
-            settings.advanced.dithering.is_enabled,
-        )?
-    };
+/// Opens and decodes an image file, optionally applying its EXIF orientation.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or decoded.
+pub(crate) fn load_image(path: &Path, auto_orient: bool) -> error::Result<DynamicImage> {
+    let (mut img, orientation) = decode_image(path)?;
+    if auto_orient {
+        img.apply_orientation(orientation);
+    }
+    Ok(img)
+}
 
-    // 5. Process the image pixels into styled characters in parallel.
-    let mut rows: Vec<String> = vec![String::new(); h];
-    rows.par_iter_mut().enumerate().for_each(|(y, row_buf)| {
-        *row_buf = processing::process_row(y, w, &processed_img, settings);
-    });
+/// Opens and decodes an image file, returning it with its EXIF orientation unapplied.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or decoded.
+pub(crate) fn decode_image(path: &Path) -> error::Result<(DynamicImage, Orientation)> {
+    decode(image::ImageReader::open(path)?.with_guessed_format()?)
+}
 
-    Ok(rows.join("\n"))
+/// Decodes an image, returning it with its EXIF orientation unapplied.
+///
+/// If the orientation cannot be read, the image is treated as upright.
+///
+/// # Errors
+///
+/// Returns an error if the image cannot be decoded.
+fn decode<R: BufRead + Seek>(
+    reader: image::ImageReader<R>,
+) -> error::Result<(DynamicImage, Orientation)> {
+    let mut decoder = reader.into_decoder()?;
+    // Unreadable EXIF metadata should not prevent decoding the pixels, and
+    // callers that do not auto-orient never look at the orientation.
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    Ok((DynamicImage::from_decoder(decoder)?, orientation))
 }
 
 /// Calculates the target dimensions in characters based on size settings.
 ///
 /// This internal helper computes the final character grid size, respecting
-/// the original image's aspect ratio when `SizeMode` is `Fit`.
-fn calculate_dimensions(
+/// the original image's aspect ratio in every mode except `Exact` and `Fill`.
+/// If `allow_upscale` is `false`, the result is shrunk (preserving its aspect
+/// ratio) so that the 2x2-pixel cell grid never exceeds the source resolution.
+pub(crate) fn calculate_dimensions(
     img_w: u32,
     img_h: u32,
     width: usize,
     height: usize,
     mode: SizeMode,
     char_ratio: f32,
+    allow_upscale: bool,
 ) -> (usize, usize) {
-    if mode == SizeMode::Exact {
-        return (width.max(1), height.max(1));
-    }
     let char_ratio = char_ratio.max(0.01); // avoid div-by-near-zero
 
     let img_w_f = img_w as f32;
@@ -191,92 +264,42 @@ fn calculate_dimensions(
     let fit_height = width_f * (img_h_f / img_w_f) * char_ratio;
     let fit_width = (height_f * (img_w_f / img_h_f)) / char_ratio;
 
-    let (w_calc, h_calc) = if fit_height > height_f {
-        (fit_width.round() as usize, height)
+    let (w_calc, h_calc) = match mode {
+        SizeMode::Exact | SizeMode::Fill => (width, height),
+        SizeMode::Width => (width, fit_height.round() as usize),
+        SizeMode::Height => (fit_width.round() as usize, height),
+        SizeMode::Fit => {
+            if fit_height > height_f {
+                (fit_width.round() as usize, height)
+            } else {
+                (width, fit_height.round() as usize)
+            }
+        }
+    };
+    let (mut w_calc, mut h_calc) = (w_calc.max(1), h_calc.max(1));
+
+    // Each character samples a 2x2 pixel block, so the source must provide at
+    // least twice the character count in each direction to avoid upscaling.
+    // In `Fill` mode only the part of the source left after cropping to the
+    // grid's aspect ratio is sampled.
+    let (src_w, src_h) = if mode == SizeMode::Fill {
+        let aspect = w_calc as f32 * char_ratio / h_calc as f32;
+        if img_w_f / img_h_f > aspect {
+            (img_h_f * aspect, img_h_f)
+        } else {
+            (img_w_f, img_w_f / aspect)
+        }
     } else {
-        (width, fit_height.round() as usize)
+        (img_w_f, img_h_f)
     };
-    (w_calc.max(1), h_calc.max(1))
-}
-
-/// Reduces the image's color count to a fixed palette using `imagequant`.
-///
-/// This function also applies dithering if enabled. It's a necessary step
-/// for non-truecolor terminals to approximate the original image's colors.
-///
-/// # Errors
-///
-/// Returns a `Processing` error if any step in the `imagequant` pipeline fails.
-fn quantize_with_imagequant(
-    rgb: &image::RgbImage,
-    palette_rgb: &[image::Rgb<u8>],
-    dithering_enabled: bool,
-) -> error::Result<image::RgbImage> {
-    let (w, h) = rgb.dimensions();
-
-    // `imagequant` requires an RGBA buffer, so we convert the input.
-    let rgba_pixels: Vec<LiqRGBA> = rgb
-        .pixels()
-        .map(|p| LiqRGBA {
-            r: p[0],
-            g: p[1],
-            b: p[2],
-            a: 255,
-        })
-        .collect();
-
-    // The fixed palette must also be in RGBA format.
-    let fixed_palette: Vec<LiqRGBA> = palette_rgb
-        .iter()
-        .map(|p| LiqRGBA {
-            r: p[0],
-            g: p[1],
-            b: p[2],
-            a: 255,
-        })
-        .collect();
-
-    let attr = LiqAttr::new();
-    let mut liq_img = LiqImage::new_borrowed(
-        &attr,
-        &rgba_pixels,
-        w as usize,
-        h as usize,
-        0.0, // Treat as sRGB, as recommended by imagequant docs
-    )
-    .map_err(|e| {
-        error::AnsiImageError::Processing(format!("imagequant new_image failed: {e:?}"))
-    })?;
-
-    // Use the provided fixed palette instead of generating a new one.
-    let mut res = LiqResult::from_palette(&attr, &fixed_palette, 0.0).map_err(|e| {
-        error::AnsiImageError::Processing(format!("imagequant from_palette failed: {e:?}"))
-    })?;
-
-    // Dithering strength: 1.0 = strongest, 0.0 = none.
-    let level = if dithering_enabled { 1.0 } else { 0.0 };
-    res.set_dithering_level(level).map_err(|e| {
-        error::AnsiImageError::Processing(format!("imagequant set_dithering_level failed: {e:?}"))
-    })?;
-
-    // Remap the image to the palette and get the resulting pixel indices.
-    let (out_palette, indices) = res.remapped(&mut liq_img).map_err(|e| {
-        error::AnsiImageError::Processing(format!("imagequant remapped failed: {e:?}"))
-    })?;
-
-    // Expand the indexed pixels back into a full RGB image for the next processing stage.
-    let mut out_buffer = Vec::with_capacity(indices.len() * 3);
-    for &idx in &indices {
-        let c = out_palette[idx as usize];
-        out_buffer.extend_from_slice(&[c.r, c.g, c.b]);
+    if !allow_upscale && ((w_calc * 2) as f32 > src_w || (h_calc * 2) as f32 > src_h) {
+        let scale = (src_w / (w_calc * 2) as f32).min(src_h / (h_calc * 2) as f32);
+        w_calc = ((w_calc as f32 * scale).floor() as usize).max(1);
+        h_calc = ((h_calc as f32 * scale).floor() as usize).max(1);
     }
-
-    image::RgbImage::from_vec(w, h, out_buffer).ok_or_else(|| {
-        error::AnsiImageError::Processing(
-            "Failed to construct RgbImage from quantized buffer".into(),
-        )
-    })
+    (w_calc, h_calc)
 }
+
 #[cfg(test)]
 mod tests {
     use super::calculate_dimensions;
@@ -285,7 +308,7 @@ mod tests {
     #[test]
     fn dims_never_zero_exact() {
         assert_eq!(
-            calculate_dimensions(100, 100, 0, 0, SizeMode::Exact, 0.5),
+            calculate_dimensions(100, 100, 0, 0, SizeMode::Exact, 0.5, true),
             (1, 1)
         );
     }
@@ -293,8 +316,39 @@ mod tests {
     #[test]
     fn dims_never_zero_fit() {
         assert_eq!(
-            calculate_dimensions(100, 100, 0, 0, SizeMode::Fit, 0.5),
+            calculate_dimensions(100, 100, 0, 0, SizeMode::Fit, 0.5, true),
             (1, 1)
         );
     }
+
+    #[test]
+    fn dims_single_axis_and_upscale() {
+        assert_eq!(
+            calculate_dimensions(200, 100, 40, 0, SizeMode::Width, 0.5, true),
+            (40, 10)
+        );
+        assert_eq!(
+            calculate_dimensions(200, 100, 0, 10, SizeMode::Height, 0.5, true),
+            (40, 10)
+        );
+        assert_eq!(
+            calculate_dimensions(200, 100, 80, 40, SizeMode::Fill, 0.5, true),
+            (80, 40)
+        );
+        // A 16x16 icon is never blown up past 8x8 cells (16x16 sampled pixels).
+        assert_eq!(
+            calculate_dimensions(16, 16, 80, 40, SizeMode::Exact, 0.5, false),
+            (8, 4)
+        );
+    }
+
+    #[test]
+    fn fill_upscale_limit_uses_cropped_source() {
+        // Filling 80x80 cells crops a 200x100 image to 50x100 pixels, which
+        // only covers 25x25 cells without upscaling.
+        assert_eq!(
+            calculate_dimensions(200, 100, 80, 80, SizeMode::Fill, 0.5, false),
+            (25, 25)
+        );
+    }
 }
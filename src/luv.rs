@@ -0,0 +1,144 @@
+//! Fast conversion of 8-bit sRGB pixels to L*u*v*.
+//!
+//! Every character looks at four pixels, so this conversion runs more often
+//! than anything else in the pipeline. Instead of decoding the sRGB transfer
+//! function with `powf` for every channel, the 256 possible channel values are
+//! linearized once into a table. Whole rows are then converted in batches, with
+//! the XYZ and L*u*v* arithmetic in tight loops over arrays that the compiler
+//! can vectorize.
+//!
+//! The results match the `palette` crate's conversion to within rounding.
+
+use std::sync::LazyLock;
+
+use palette::{Srgb, white_point::WhitePoint};
+
+use crate::processing::LuvColor;
+
+/// The linear-light value of each 8-bit sRGB channel value.
+static LINEAR: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    std::array::from_fn(|i| Srgb::new(i as f32 / 255.0, 0.0, 0.0).into_linear().red)
+});
+
+/// The linear sRGB to XYZ matrix for the D65 white point, in row-major order.
+const RGB_TO_XYZ: [f32; 9] = [
+    0.412_456_4,
+    0.357_576_1,
+    0.180_437_5,
+    0.212_672_9,
+    0.715_152_2,
+    0.072_175,
+    0.019_333_9,
+    0.119_192,
+    0.950_304_1,
+];
+/// The CIE constant κ, used for the linear segment of L* near black.
+const KAPPA: f32 = 24_389.0 / 27.0;
+/// The CIE constant ε, where L* switches from the linear segment to the cube root.
+const EPSILON: f32 = 216.0 / 24_389.0;
+/// The number of pixels converted together in a batch.
+const BATCH: usize = 64;
+
+/// The u′ and v′ chromaticity coordinates of the D65 white point.
+static WHITE_PRIME: LazyLock<(f32, f32)> = LazyLock::new(|| {
+    let w = <palette::white_point::D65 as WhitePoint<f32>>::get_xyz();
+    let recip: f32 = (w.x + 15.0 * w.y + 3.0 * w.z).recip();
+    (4.0 * w.x * recip, 9.0 * w.y * recip)
+});
+
+/// Converts an 8-bit sRGB color to L*u*v*.
+#[inline]
+pub(crate) fn rgb_to_luv(rgb: [u8; 3]) -> LuvColor {
+    let (y, u_prime, v_prime) = chromaticity(rgb);
+    let l = lightness(y);
+    finish(l, u_prime, v_prime)
+}
+
+/// Converts a row of packed 8-bit RGB pixels to L*u*v*, appending the colors
+/// to `out`.
+pub(crate) fn row_to_luv(pixels: &[u8], out: &mut Vec<LuvColor>) {
+    out.reserve(pixels.len() / 3);
+    for batch in pixels.chunks(BATCH * 3) {
+        let len = batch.len() / 3;
+        let mut y = [0.0f32; BATCH];
+        let mut u_prime = [0.0f32; BATCH];
+        let mut v_prime = [0.0f32; BATCH];
+        for (i, p) in batch.chunks_exact(3).enumerate() {
+            (y[i], u_prime[i], v_prime[i]) = chromaticity([p[0], p[1], p[2]]);
+        }
+        let mut l = [0.0f32; BATCH];
+        for (l, &y) in l[..len].iter_mut().zip(&y[..len]) {
+            *l = lightness(y);
+        }
+        out.extend((0..len).map(|i| finish(l[i], u_prime[i], v_prime[i])));
+    }
+}
+
+/// Returns the relative luminance Y and the u′ and v′ chromaticity
+/// coordinates of an 8-bit sRGB color.
+#[inline]
+fn chromaticity([r, g, b]: [u8; 3]) -> (f32, f32, f32) {
+    let [r, g, b] = [r, g, b].map(|c| LINEAR[usize::from(c)]);
+    let m = &RGB_TO_XYZ;
+    let x = m[0] * r + m[1] * g + m[2] * b;
+    let y = m[3] * r + m[4] * g + m[5] * b;
+    let z = m[6] * r + m[7] * g + m[8] * b;
+    let denom = x + 15.0 * y + 3.0 * z;
+    // Black has no chromaticity; its u* and v* are zero because L* is.
+    let recip = if denom > 0.0 { denom.recip() } else { 0.0 };
+    (y, 4.0 * x * recip, 9.0 * y * recip)
+}
+
+/// Returns L* for a relative luminance Y (with a white Y of 1).
+#[inline]
+fn lightness(y: f32) -> f32 {
+    if y > EPSILON {
+        116.0 * y.cbrt() - 16.0
+    } else {
+        KAPPA * y
+    }
+}
+
+/// Assembles a L*u*v* color from L* and the chromaticity coordinates.
+#[inline]
+fn finish(l: f32, u_prime: f32, v_prime: f32) -> LuvColor {
+    let (u_white, v_white) = *WHITE_PRIME;
+    LuvColor::new(
+        l,
+        13.0 * l * (u_prime - u_white),
+        13.0 * l * (v_prime - v_white),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use palette::{Srgb, convert::FromColorUnclamped as _};
+
+    use super::{rgb_to_luv, row_to_luv};
+    use crate::processing::{LuvColor, luv_distance};
+
+    #[test]
+    fn matches_palette_conversion() {
+        let mut pixels = Vec::new();
+        for r in (0..=255u8).step_by(3) {
+            for g in (0..=255u8).step_by(5) {
+                for b in (0..=255u8).step_by(7) {
+                    pixels.extend_from_slice(&[r, g, b]);
+                }
+            }
+        }
+        let mut row = Vec::new();
+        row_to_luv(&pixels, &mut row);
+        assert_eq!(row.len(), pixels.len() / 3);
+
+        for (p, fast) in pixels.chunks_exact(3).zip(row) {
+            let exact =
+                LuvColor::from_color_unclamped(Srgb::new(p[0], p[1], p[2]).into_format::<f32>());
+            assert!(
+                luv_distance(exact, fast) < 1e-3,
+                "{p:?}: {exact:?} {fast:?}"
+            );
+            assert_eq!(fast, rgb_to_luv([p[0], p[1], p[2]]));
+        }
+    }
+}
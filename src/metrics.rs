@@ -0,0 +1,277 @@
+//! Objective quality metrics for rendered output.
+//!
+//! A character grid is turned back into an approximate image by painting each
+//! cell's 2x2 sub-pixels with the colors its character shows, the same pattern
+//! the converter uses to choose characters. That image is then compared with
+//! the resized source using PSNR, SSIM and the mean color difference, so that
+//! character sets and palettes can be compared and tracked over time.
+
+use image::{DynamicImage, Rgb, RgbImage};
+
+use crate::{
+    canvas::Canvas,
+    converter::Grid,
+    error,
+    filters::gaussian_blur,
+    processing::{blend, block_pattern, luv_distance, luv_to_rgb, pixel_to_luv},
+    settings::{CharacterMode, Settings},
+};
+
+/// The color assumed for cells without a foreground color.
+const DEFAULT_FG: Rgb<u8> = Rgb([255, 255, 255]);
+/// The color assumed for cells without a background color.
+const DEFAULT_BG: Rgb<u8> = Rgb([0, 0, 0]);
+/// The fraction of a cell covered by the densest character of a brightness ramp.
+///
+/// Even the boldest glyphs, such as `@` or `#`, leave roughly half of their cell
+/// empty. Without this limit a dense character would be scored as a solid
+/// block of its foreground color, overrating text characters against blocks.
+const MAX_GLYPH_COVERAGE: f32 = 0.5;
+/// The standard deviation of the Gaussian window used for SSIM.
+const SSIM_SIGMA: f32 = 1.5;
+
+/// Quality metrics of a rendered image against its source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    /// Peak signal-to-noise ratio over the RGB channels, in decibels. Higher is
+    /// better, and identical images give infinity.
+    pub psnr: f64,
+    /// Mean structural similarity of the luma channels, where `1.0` means
+    /// identical structure.
+    pub ssim: f64,
+    /// Mean color difference per pixel, measured as the Euclidean distance in
+    /// L*u*v* (CIE76-style ΔE). Lower is better.
+    pub delta_e: f64,
+}
+
+/// Converts an image and measures how closely the output represents it.
+///
+/// The reference is the resized and adjusted image just before quantization,
+/// so the metrics reflect the character set, color mode and palette rather
+/// than the output size. Padding, overlays and frames are not included.
+///
+/// # Errors
+///
+/// This function can fail for the same reasons as
+/// [`convert_image_to_canvas`](crate::convert_image_to_canvas).
+pub fn measure(img: &DynamicImage, settings: &Settings) -> error::Result<Metrics> {
+    measure_grid(img, settings).map(|(metrics, _)| metrics)
+}
+
+/// Converts an image and measures the output like [`measure`], also returning
+/// the converted grid.
+///
+/// # Errors
+///
+/// This function can fail for the same reasons as [`measure`].
+pub(crate) fn measure_grid(
+    img: &DynamicImage,
+    settings: &Settings,
+) -> error::Result<(Metrics, Grid)> {
+    let grid = crate::convert_image_to_grid(img, settings)?;
+    let rendered = reconstruct(&grid.canvas, &settings.characters.mode);
+    Ok((evaluate(&grid.source, &rendered)?, grid))
+}
+
+/// Reconstructs the approximate image a character grid displays, at 2x2
+/// pixels per cell.
+///
+/// Block characters are painted with their foreground/background pattern.
+/// Other characters are drawn as a blend of both colors, weighted by the
+/// character's position in the brightness ramp of `mode` (or the middle of
+/// the ramp if it is not in it), up to half of the foreground for the densest
+/// character. Missing colors are assumed to be white for the foreground and
+/// black for the background.
+#[must_use]
+pub fn reconstruct(canvas: &Canvas, mode: &CharacterMode) -> RgbImage {
+    let ramp: &[char] = match mode {
+        CharacterMode::Ascii(cs) => cs.as_slice(),
+        CharacterMode::Custom(v) => v,
+        CharacterMode::Unicode(_) => &[],
+    };
+
+    let (w, h) = (canvas.width(), canvas.height());
+    let mut img = RgbImage::new((w * 2) as u32, (h * 2) as u32);
+    for y in 0..h {
+        for x in 0..w {
+            let cell = canvas.get(x, y).unwrap_or_default();
+            let fg = pixel_to_luv(cell.fg.unwrap_or(DEFAULT_FG));
+            let bg = pixel_to_luv(cell.bg.unwrap_or(DEFAULT_BG));
+
+            let pattern = if matches!(mode, CharacterMode::Unicode(_)) {
+                block_pattern(cell.character, fg, bg)
+            } else {
+                let coverage = ramp
+                    .iter()
+                    .position(|&c| c == cell.character)
+                    .map_or(0.5, |i| i as f32 / (ramp.len() - 1).max(1) as f32)
+                    * MAX_GLYPH_COVERAGE;
+                [blend(fg, bg, coverage); 4]
+            };
+
+            for (i, luv) in pattern.into_iter().enumerate() {
+                let (r, g, b) = luv_to_rgb(luv);
+                img.put_pixel(
+                    (x * 2 + i % 2) as u32,
+                    (y * 2 + i / 2) as u32,
+                    Rgb([r, g, b]),
+                );
+            }
+        }
+    }
+    img
+}
+
+/// Computes quality metrics of `candidate` against `reference`.
+///
+/// # Errors
+///
+/// Returns a `Processing` error if the images have different dimensions.
+pub fn evaluate(reference: &RgbImage, candidate: &RgbImage) -> error::Result<Metrics> {
+    if reference.dimensions() != candidate.dimensions() {
+        return Err(error::AnsiImageError::Processing(format!(
+            "Cannot compare a {}x{} image with a {}x{} image.",
+            reference.width(),
+            reference.height(),
+            candidate.width(),
+            candidate.height(),
+        )));
+    }
+    let pixels = reference.width() as usize * reference.height() as usize;
+    if pixels == 0 {
+        return Ok(Metrics {
+            psnr: f64::INFINITY,
+            ssim: 1.0,
+            delta_e: 0.0,
+        });
+    }
+
+    let squared_error: f64 = reference
+        .as_raw()
+        .iter()
+        .zip(candidate.as_raw())
+        .map(|(&a, &b)| (f64::from(a) - f64::from(b)).powi(2))
+        .sum();
+    let mse = squared_error / (pixels * 3) as f64;
+    let psnr = if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mse).log10()
+    };
+
+    let delta_e = reference
+        .pixels()
+        .zip(candidate.pixels())
+        .map(|(&a, &b)| f64::from(luv_distance(pixel_to_luv(a), pixel_to_luv(b))))
+        .sum::<f64>()
+        / pixels as f64;
+
+    Ok(Metrics {
+        psnr,
+        ssim: ssim(reference, candidate),
+        delta_e,
+    })
+}
+
+/// Computes the mean structural similarity of the luma channels of two images
+/// of equal size, using a Gaussian window.
+fn ssim(a: &RgbImage, b: &RgbImage) -> f64 {
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+
+    let (w, h) = (a.width() as usize, a.height() as usize);
+    let luma = |img: &RgbImage| -> Vec<f32> {
+        img.pixels()
+            .map(|p| {
+                let [r, g, b] = p.0.map(|c| f32::from(c) / 255.0);
+                0.114f32.mul_add(b, 0.299f32.mul_add(r, 0.587 * g))
+            })
+            .collect()
+    };
+    let (x, y) = (luma(a), luma(b));
+    let product =
+        |p: &[f32], q: &[f32]| -> Vec<f32> { p.iter().zip(q).map(|(a, b)| a * b).collect() };
+
+    let mu_x = gaussian_blur(&x, w, h, SSIM_SIGMA);
+    let mu_y = gaussian_blur(&y, w, h, SSIM_SIGMA);
+    let xx = gaussian_blur(&product(&x, &x), w, h, SSIM_SIGMA);
+    let yy = gaussian_blur(&product(&y, &y), w, h, SSIM_SIGMA);
+    let xy = gaussian_blur(&product(&x, &y), w, h, SSIM_SIGMA);
+
+    let total: f64 = (0..w * h)
+        .map(|i| {
+            let (mx, my) = (mu_x[i], mu_y[i]);
+            let var_x = mx.mul_add(-mx, xx[i]);
+            let var_y = my.mul_add(-my, yy[i]);
+            let cov = mx.mul_add(-my, xy[i]);
+            let numerator = (2.0 * mx).mul_add(my, C1) * 2.0f32.mul_add(cov, C2);
+            let denominator = mx.mul_add(mx, my.mul_add(my, C1)) * (var_x + var_y + C2);
+            f64::from(numerator / denominator)
+        })
+        .sum();
+    total / (w * h) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    use super::{evaluate, measure, reconstruct};
+    use crate::{
+        canvas::{Canvas, Cell},
+        settings::{CharacterMode, Characters, Settings, Size, UnicodeCharSet},
+    };
+
+    #[test]
+    fn identical_and_block_metrics() {
+        let img = RgbImage::from_fn(8, 8, |x, y| Rgb([(x * 30) as u8, (y * 30) as u8, 90]));
+        let metrics = evaluate(&img, &img).unwrap();
+        assert!(metrics.psnr.is_infinite());
+        assert!((metrics.ssim - 1.0).abs() < 1e-4);
+        assert!(metrics.delta_e < 1e-6);
+        assert!(evaluate(&img, &RgbImage::new(4, 4)).is_err());
+
+        // Quarter blocks reproduce a sharp vertical edge that full blocks blur.
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, _| {
+            if x % 4 < 1 {
+                Rgb([255; 3])
+            } else {
+                Rgb([0; 3])
+            }
+        }));
+        let settings = |charset| Settings {
+            size: Size {
+                width: 8,
+                height: 8,
+                ..Default::default()
+            },
+            characters: Characters {
+                mode: CharacterMode::Unicode(charset),
+                aspect_ratio: 1.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let quarter = measure(&img, &settings(UnicodeCharSet::Quarter)).unwrap();
+        let full = measure(&img, &settings(UnicodeCharSet::Full)).unwrap();
+        assert!(quarter.psnr > full.psnr);
+        assert!(quarter.ssim > full.ssim);
+        assert!(quarter.delta_e < full.delta_e);
+    }
+
+    #[test]
+    fn dense_characters_cover_half_a_cell() {
+        let cell = |character| Cell {
+            character,
+            fg: Some(Rgb([255; 3])),
+            bg: Some(Rgb([0; 3])),
+        };
+        let canvas = Canvas::from_rows(vec![vec![cell('#'), cell(' ')]]);
+        let img = reconstruct(&canvas, &CharacterMode::Custom(vec![' ', '.', '#']));
+        // The densest character shows a mid gray rather than its full
+        // foreground color, and the lightest one shows the background.
+        let gray = img.get_pixel(0, 0).0[0];
+        assert!((100..150).contains(&gray), "{gray}");
+        assert_eq!(img.get_pixel(2, 0).0, [0; 3]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Grid layouts of several converted images.
+//!
+//! A montage converts every image with the same [`Settings`], centers each
+//! result in an equally sized tile, and arranges the tiles in rows with gutters
+//! and optional file-name labels. This is handy for browsing a directory of
+//! images in the terminal.
+
+use std::path::Path;
+
+use crate::{
+    canvas::{Canvas, Cell},
+    error,
+    parallel::prelude::*,
+    settings::{Alignment, Montage, PaddingFill, Settings},
+};
+
+/// Converts several image files and lays them out as a [`Montage`].
+///
+/// The images are converted in parallel and labeled with their file names.
+///
+/// # Errors
+///
+/// Returns an `InvalidSettings` error if `montage.columns` is zero, or the
+/// first error encountered while converting any of the images.
+pub fn convert_montage<P: AsRef<Path> + Sync>(
+    paths: &[P],
+    settings: &Settings,
+    montage: &Montage,
+) -> error::Result<Canvas> {
+    validate(montage)?;
+
+    let tiles = paths
+        .par_iter()
+        .map(|path| {
+            let path = path.as_ref();
+            let label = path
+                .file_name()
+                .map_or_else(|| path.to_string_lossy(), |n| n.to_string_lossy())
+                .into_owned();
+            Ok((crate::convert_to_canvas(path, settings)?, label))
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+
+    Ok(compose(&tiles, montage))
+}
+
+/// Checks that a [`Montage`] layout has at least one column.
+pub(crate) fn validate(montage: &Montage) -> error::Result<()> {
+    if montage.columns == 0 {
+        return Err(error::AnsiImageError::InvalidSettings(
+            "Montage columns must be at least 1.".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lays out already converted tiles in a grid, each with a label.
+///
+/// Every tile is centered in a cell as large as the widest and tallest tile.
+/// Labels are centered below their tile and truncated with `…` to fit its
+/// width. Gutters and any unused space are filled with blank cells.
+#[must_use]
+pub fn compose(tiles: &[(Canvas, String)], montage: &Montage) -> Canvas {
+    layout(tiles, montage, false)
+}
+
+/// Lays out tiles in a grid, with labels either above (`headers`) or below them.
+pub(crate) fn layout(tiles: &[(Canvas, String)], montage: &Montage, headers: bool) -> Canvas {
+    let columns = montage.columns.max(1).min(tiles.len());
+    if columns == 0 {
+        return Canvas::new(0, 0, None);
+    }
+    let rows = tiles.len().div_ceil(columns);
+
+    let tile_w = tiles.iter().map(|(c, _)| c.width()).max().unwrap_or(0);
+    let tile_h = tiles.iter().map(|(c, _)| c.height()).max().unwrap_or(0);
+    let label_h = usize::from(montage.labels);
+    let cell_h = tile_h + label_h;
+    let (tile_dy, label_dy) = if headers { (label_h, 0) } else { (0, tile_h) };
+
+    let width = columns * tile_w + (columns - 1) * montage.column_gap;
+    let height = rows * cell_h + (rows - 1) * montage.row_gap;
+    let mut grid = Canvas::new(width, height, Some(Cell::BLANK));
+
+    for (i, (tile, label)) in tiles.iter().enumerate() {
+        let x = (i % columns) * (tile_w + montage.column_gap);
+        let y = (i / columns) * (cell_h + montage.row_gap);
+        let padded = tile.padded(
+            tile_w,
+            tile_h,
+            Alignment::Center,
+            Alignment::Center,
+            PaddingFill::Default,
+        );
+        grid.draw(&padded, x, y + tile_dy);
+
+        if montage.labels {
+            let mut text: Vec<char> = label.chars().collect();
+            if text.len() > tile_w {
+                text.truncate(tile_w.saturating_sub(1));
+                text.push('…');
+                text.truncate(tile_w);
+            }
+            let lx = x + Alignment::Center.offset(text.len(), tile_w);
+            for (dx, &character) in text.iter().enumerate() {
+                grid.set(
+                    lx + dx,
+                    y + label_dy,
+                    Some(Cell {
+                        character,
+                        fg: montage.label_color,
+                        bg: None,
+                    }),
+                );
+            }
+        }
+    }
+
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::compose;
+    use crate::{
+        canvas::{Canvas, Cell},
+        settings::Montage,
+    };
+
+    #[test]
+    fn tiles_are_centered_and_labeled() {
+        let red = Some(Cell::solid(Rgb([255, 0, 0])));
+        let tiles = vec![
+            (Canvas::new(4, 2, red), "wide.png".to_string()),
+            (Canvas::new(2, 1, red), "b".to_string()),
+            (Canvas::new(2, 2, red), "c".to_string()),
+        ];
+        let montage = Montage {
+            columns: 2,
+            column_gap: 1,
+            row_gap: 1,
+            ..Default::default()
+        };
+        let grid = compose(&tiles, &montage);
+
+        // Two columns of 4-wide tiles with a one-cell gutter; two rows of
+        // 2-high tiles plus a label row, separated by a one-row gap.
+        assert_eq!((grid.width(), grid.height()), (9, 7));
+
+        // The long label is truncated to the tile width.
+        let label: String = (0..4).map(|x| grid.get(x, 2).unwrap().character).collect();
+        assert_eq!(label, "wid…");
+
+        // The smaller tile is centered within its cell.
+        assert_eq!(grid.get(5, 0), Some(Cell::BLANK));
+        assert_eq!(grid.get(6, 0), red);
+        assert_eq!(grid.get(6, 1), Some(Cell::BLANK));
+        assert_eq!(grid.get(6, 2).unwrap().character, 'b');
+
+        // The third tile starts the second row.
+        assert_eq!(grid.get(1, 4), red);
+        assert_eq!(grid.get(0, 4), Some(Cell::BLANK));
+    }
+}
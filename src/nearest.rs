@@ -0,0 +1,249 @@
+//! Nearest-color search in a palette.
+//!
+//! Matching a block against a palette is the innermost loop of paletted
+//! conversions: every candidate of every cell looks up its closest palette
+//! colors. Small palettes are scanned linearly, which is fastest for a handful
+//! of colors. Larger palettes, such as the 256 xterm colors, are indexed with a
+//! k-d tree over L*u*v*, which finds the same color while skipping most of the
+//! palette.
+
+use crate::processing::LuvColor;
+
+/// The palette size from which a k-d tree is used instead of a linear scan.
+const TREE_THRESHOLD: usize = 32;
+
+/// A palette of L*u*v* colors prepared for nearest-color queries.
+///
+/// The search method is chosen from the palette size when the index is
+/// created. Both methods return the first of several equally close colors, so
+/// the results do not depend on the method.
+#[derive(Debug, Clone)]
+pub struct PaletteIndex {
+    /// The palette colors as `[L, u, v]` components, in palette order.
+    points: Vec<[f32; 3]>,
+    /// The k-d tree, if the palette is large enough to use one.
+    tree: Option<KdTree>,
+}
+
+/// A balanced k-d tree stored as a permutation of the palette.
+///
+/// The node of a range `lo..hi` of `order` is its middle element, splitting on
+/// `axes` at the same position; the halves on either side are its subtrees.
+#[derive(Debug, Clone)]
+struct KdTree {
+    /// Palette indices in tree order.
+    order: Vec<usize>,
+    /// The split axis of the node at each position of `order`.
+    axes: Vec<u8>,
+}
+
+impl PaletteIndex {
+    /// Creates an index for the given palette colors.
+    #[must_use]
+    pub fn new(colors: &[LuvColor]) -> Self {
+        let points: Vec<[f32; 3]> = colors.iter().map(|c| [c.l, c.u, c.v]).collect();
+        let tree = (points.len() >= TREE_THRESHOLD).then(|| KdTree::build(&points));
+        Self { points, tree }
+    }
+
+    /// Returns the number of colors in the palette.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if the palette has no colors.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns the palette color at `index`, if any.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<LuvColor> {
+        self.points
+            .get(index)
+            .map(|&[l, u, v]| LuvColor::new(l, u, v))
+    }
+
+    /// Finds the palette color closest to `color`, returning its index and
+    /// value, or `None` if the palette is empty.
+    #[must_use]
+    pub fn nearest(&self, color: LuvColor) -> Option<(usize, LuvColor)> {
+        self.search(color, None)
+    }
+
+    /// Finds the palette color closest to `color`, other than the one at
+    /// `excluded`, returning its index and value.
+    #[must_use]
+    pub fn nearest_excluding(&self, color: LuvColor, excluded: usize) -> Option<(usize, LuvColor)> {
+        self.search(color, Some(excluded))
+    }
+
+    /// Runs a nearest-color query with the index's search method.
+    fn search(&self, color: LuvColor, excluded: Option<usize>) -> Option<(usize, LuvColor)> {
+        let target = [color.l, color.u, color.v];
+        let mut best = (f32::INFINITY, usize::MAX);
+        if let Some(tree) = &self.tree {
+            tree.search(
+                &self.points,
+                target,
+                excluded,
+                0,
+                self.points.len(),
+                &mut best,
+            );
+        } else {
+            for (i, &point) in self.points.iter().enumerate() {
+                consider(&mut best, i, point, target, excluded);
+            }
+        }
+        self.get(best.1).map(|c| (best.1, c))
+    }
+}
+
+impl KdTree {
+    /// Builds a balanced tree over `points`, splitting each node on the axis
+    /// with the widest spread.
+    fn build(points: &[[f32; 3]]) -> Self {
+        let mut tree = Self {
+            order: (0..points.len()).collect(),
+            axes: vec![0; points.len()],
+        };
+        tree.build_range(points, 0, points.len());
+        tree
+    }
+
+    /// Arranges the range `lo..hi` of `order` into a subtree.
+    fn build_range(&mut self, points: &[[f32; 3]], lo: usize, hi: usize) {
+        if hi - lo <= 1 {
+            return;
+        }
+        let spread = |axis: usize| {
+            let (min, max) = self.order[lo..hi]
+                .iter()
+                .map(|&i| points[i][axis])
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+                    (min.min(x), max.max(x))
+                });
+            max - min
+        };
+        let axis = (0..3)
+            .max_by(|&a, &b| spread(a).total_cmp(&spread(b)))
+            .unwrap_or(0);
+
+        let mid = lo + (hi - lo) / 2;
+        self.order[lo..hi].select_nth_unstable_by(mid - lo, |&a, &b| {
+            points[a][axis].total_cmp(&points[b][axis])
+        });
+        self.axes[mid] = axis as u8;
+        self.build_range(points, lo, mid);
+        self.build_range(points, mid + 1, hi);
+    }
+
+    /// Searches the subtree of the range `lo..hi` of `order`, updating `best`
+    /// with the distance and index of the closest color found.
+    fn search(
+        &self,
+        points: &[[f32; 3]],
+        target: [f32; 3],
+        excluded: Option<usize>,
+        lo: usize,
+        hi: usize,
+        best: &mut (f32, usize),
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let index = self.order[mid];
+        let point = points[index];
+        consider(best, index, point, target, excluded);
+
+        let axis = usize::from(self.axes[mid]);
+        let offset = target[axis] - point[axis];
+        let ((near_lo, near_hi), (far_lo, far_hi)) = if offset < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(points, target, excluded, near_lo, near_hi, best);
+        // Colors at exactly the best distance are still visited, so that ties
+        // resolve to the lowest index like a linear scan.
+        if offset.abs() <= best.0 {
+            self.search(points, target, excluded, far_lo, far_hi, best);
+        }
+    }
+}
+
+/// Replaces `best` with the palette color at `index` if it is closer to
+/// `target`, or equally close with a lower index.
+#[inline]
+fn consider(
+    best: &mut (f32, usize),
+    index: usize,
+    point: [f32; 3],
+    target: [f32; 3],
+    excluded: Option<usize>,
+) {
+    if excluded == Some(index) {
+        return;
+    }
+    let [dl, du, dv] = std::array::from_fn(|i| target[i] - point[i]);
+    // The square root keeps ties between nearly equidistant colors identical to
+    // comparisons of `luv_distance`.
+    let distance = dv.mul_add(dv, dl.mul_add(dl, du * du)).sqrt();
+    if distance < best.0 || (distance == best.0 && index < best.1) {
+        *best = (distance, index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PaletteIndex;
+    use crate::processing::{LuvColor, luv_distance, pixel_to_luv};
+
+    #[test]
+    fn tree_matches_linear_scan() {
+        // A 6x6x6 color cube like the xterm palette, plus grays.
+        let levels = [0u8, 95, 135, 175, 215, 255];
+        let mut palette: Vec<LuvColor> = (0..216)
+            .map(|i| {
+                pixel_to_luv(image::Rgb([
+                    levels[i / 36],
+                    levels[i / 6 % 6],
+                    levels[i % 6],
+                ]))
+            })
+            .collect();
+        palette.extend((0..24).map(|i| pixel_to_luv(image::Rgb([8 + i * 10; 3]))));
+        let index = PaletteIndex::new(&palette);
+        assert!(index.tree.is_some());
+
+        let linear = |color: LuvColor, excluded: Option<usize>| {
+            palette
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| Some(i) != excluded)
+                .map(|(i, &c)| (luv_distance(color, c), i))
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap()
+        };
+        for r in (0..=255).step_by(17) {
+            for g in (0..=255).step_by(51) {
+                for b in (0..=255).step_by(37) {
+                    let color = pixel_to_luv(image::Rgb([r as u8, g as u8, b as u8]));
+                    let (i, found) = index.nearest(color).unwrap();
+                    let (distance, _) = linear(color, None);
+                    assert!((luv_distance(color, found) - distance).abs() < 1e-4);
+
+                    let (j, found) = index.nearest_excluding(color, i).unwrap();
+                    assert_ne!(i, j);
+                    let (distance, _) = linear(color, Some(i));
+                    assert!((luv_distance(color, found) - distance).abs() < 1e-4);
+                }
+            }
+        }
+        assert!(PaletteIndex::new(&[]).nearest(palette[0]).is_none());
+    }
+}
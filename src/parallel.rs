@@ -0,0 +1,141 @@
+//! Data-parallel iteration, with a sequential stand-in.
+//!
+//! With the `parallel` feature, [`prelude`] re-exports the `rayon` traits.
+//! Without it, the same `par_*` methods are provided by traits that return
+//! ordinary sequential iterators, so the rest of the crate is written once and
+//! works on targets without threads, such as WebAssembly.
+//!
+//! Thread pools with a fixed number of threads are created on first use by
+//! [`shared_pool`] and reused by later conversions with the same count. Only
+//! the [`MAX_SHARED_POOLS`] most recently used counts are kept.
+
+#[cfg(feature = "parallel")]
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The iterator traits, imported with `use crate::parallel::prelude::*`.
+pub(crate) mod prelude {
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) use super::sequential::{
+        IntoParallelIterator, IntoParallelRefIterator, ParallelSlice, ParallelSliceMut,
+    };
+    #[cfg(feature = "parallel")]
+    pub(crate) use rayon::prelude::*;
+}
+
+/// The number of thread pools [`shared_pool`] keeps alive.
+#[cfg(feature = "parallel")]
+pub(crate) const MAX_SHARED_POOLS: usize = 4;
+
+/// Returns the shared thread pool with `threads` threads, creating it on first
+/// use.
+///
+/// Conversions through the free functions create a new converter every time,
+/// and some of them, such as the candidates of
+/// [`convert_image_auto`](crate::convert_image_auto), run in parallel. Sharing
+/// the pools keeps those from each starting and stopping their own threads.
+/// When a new count would exceed [`MAX_SHARED_POOLS`], the least recently used
+/// pool is released, and its threads stop once no converter holds it.
+#[cfg(feature = "parallel")]
+pub(crate) fn shared_pool(
+    threads: usize,
+) -> Result<Arc<rayon::ThreadPool>, rayon::ThreadPoolBuildError> {
+    // Ordered from least to most recently used.
+    static POOLS: Mutex<Vec<(usize, Arc<rayon::ThreadPool>)>> = Mutex::new(Vec::new());
+    let mut pools = POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(i) = pools.iter().position(|&(count, _)| count == threads) {
+        let entry = pools.remove(i);
+        let pool = entry.1.clone();
+        pools.push(entry);
+        return Ok(pool);
+    }
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?,
+    );
+    if pools.len() == MAX_SHARED_POOLS {
+        pools.remove(0);
+    }
+    pools.push((threads, pool.clone()));
+    Ok(pool)
+}
+
+/// Sequential versions of the `rayon` iterator methods the crate uses.
+#[cfg(not(feature = "parallel"))]
+mod sequential {
+    use std::slice::{Chunks, ChunksExact, ChunksMut, Iter};
+
+    /// Sequential `into_par_iter`.
+    pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+        /// Returns the ordinary iterator.
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    /// Sequential `par_iter`.
+    pub(crate) trait IntoParallelRefIterator<T> {
+        /// Returns an iterator over references to the elements.
+        fn par_iter(&self) -> Iter<'_, T>;
+    }
+
+    impl<T> IntoParallelRefIterator<T> for [T] {
+        fn par_iter(&self) -> Iter<'_, T> {
+            self.iter()
+        }
+    }
+
+    /// Sequential `par_chunks` and `par_chunks_exact`.
+    pub(crate) trait ParallelSlice<T> {
+        /// Returns an iterator over chunks of `size` elements.
+        fn par_chunks(&self, size: usize) -> Chunks<'_, T>;
+        /// Returns an iterator over chunks of exactly `size` elements.
+        fn par_chunks_exact(&self, size: usize) -> ChunksExact<'_, T>;
+    }
+
+    impl<T> ParallelSlice<T> for [T] {
+        fn par_chunks(&self, size: usize) -> Chunks<'_, T> {
+            self.chunks(size)
+        }
+
+        fn par_chunks_exact(&self, size: usize) -> ChunksExact<'_, T> {
+            self.chunks_exact(size)
+        }
+    }
+
+    /// Sequential `par_chunks_mut`.
+    pub(crate) trait ParallelSliceMut<T> {
+        /// Returns an iterator over mutable chunks of `size` elements.
+        fn par_chunks_mut(&mut self, size: usize) -> ChunksMut<'_, T>;
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_chunks_mut(&mut self, size: usize) -> ChunksMut<'_, T> {
+            self.chunks_mut(size)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{MAX_SHARED_POOLS, shared_pool};
+
+    #[test]
+    fn pools_are_shared_per_thread_count() {
+        let pool = shared_pool(3).unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+        assert!(Arc::ptr_eq(&pool, &shared_pool(3).unwrap()));
+        assert!(!Arc::ptr_eq(&pool, &shared_pool(2).unwrap()));
+
+        // Using more counts than are kept releases the least recently used.
+        let old = shared_pool(5).unwrap();
+        for threads in 6..6 + MAX_SHARED_POOLS {
+            shared_pool(threads).unwrap();
+        }
+        assert!(!Arc::ptr_eq(&old, &shared_pool(5).unwrap()));
+    }
+}
@@ -4,14 +4,18 @@
 //! selecting the best character to represent them, and determining the appropriate
 //! foreground and background colors according to the user's settings.
 
-use std::fmt::Write as _;
+use std::cell::RefCell;
 
 use image::{Rgb, RgbImage};
-use palette::{Luv, Srgb, convert::FromColorUnclamped, white_point::D65};
+use palette::{LinSrgb, Luv, Srgb, convert::FromColorUnclamped, white_point::D65};
 
 use crate::{
     BLACK_LUV,
-    settings::{CharacterMode, ColorMode, ColorPalette, Settings, UnicodeCharSet},
+    canvas::{self, Cell},
+    edges::EdgeMap,
+    luv,
+    nearest::PaletteIndex,
+    settings::{CharacterMode, ColorMode, Settings, UnicodeCharSet},
 };
 
 /// A type alias for the CIE L*u*v* color type used throughout the processing pipeline.
@@ -25,22 +29,20 @@ pub type LuvColor = Luv<D65, f32>;
 /// A type alias for RGB colors represented as tuples of u8 components.
 type RGB8 = (u8, u8, u8);
 
+thread_local! {
+    /// The L*u*v* pixels of the row being processed, reused between rows.
+    static LUV_ROW: RefCell<Vec<LuvColor>> = const { RefCell::new(Vec::new()) };
+}
+
 /// Converts an sRGB pixel to the L*u*v* color space.
 #[inline]
-fn pixel_to_luv(p: Rgb<u8>) -> LuvColor {
-    // Normalize sRGB u8 components to f32 values between 0.0 and 1.0.
-    let srgb = Srgb::new(
-        p.0[0] as f32 / 255.0,
-        p.0[1] as f32 / 255.0,
-        p.0[2] as f32 / 255.0,
-    );
-    // Convert the linear sRGB color to the L*u*v* color space.
-    LuvColor::from_color_unclamped(srgb)
+pub(crate) fn pixel_to_luv(p: Rgb<u8>) -> LuvColor {
+    luv::rgb_to_luv(p.0)
 }
 
 /// Converts a L*u*v* color back to a simple RGB tuple
 #[inline]
-fn luv_to_rgb(luv: LuvColor) -> RGB8 {
+pub(crate) fn luv_to_rgb(luv: LuvColor) -> RGB8 {
     // Convert back to sRGB.
     let srgb = Srgb::from_color_unclamped(luv);
     // Denormalize and clamp the f32 components to u8 values (0-255).
@@ -51,122 +53,148 @@ fn luv_to_rgb(luv: LuvColor) -> RGB8 {
     )
 }
 
+/// Converts an sRGB palette to the L*u*v* color space used for color matching.
+///
+/// The result, indexed with [`PaletteIndex::new`], is what
+/// [`process_row_with_palette`] expects as its `palette`.
+#[must_use]
+pub fn palette_to_luv(palette: &[Rgb<u8>]) -> Vec<LuvColor> {
+    palette
+        .iter()
+        .map(|&c| Srgb::new(c.0[0], c.0[1], c.0[2]).into_format())
+        .map(LuvColor::from_color_unclamped)
+        .collect()
+}
+
 /// Processes a single character row of the output image.
 ///
 /// This function iterates over the pixels corresponding to one row of the final
 /// output, processing each 2x2 pixel block into a styled character. It is
 /// designed to be called in parallel for each row to improve performance.
+/// The row is returned as ANSI escape sequences, compressed if
+/// `settings.advanced.compression` is set. To get the styled cells instead,
+/// use [`process_row_with_edges`].
+///
+/// The palette is prepared from `settings` on every call. To process many rows,
+/// prepare it once and call [`process_row_with_palette`] instead.
 pub fn process_row(
     y_char: usize,
     width_char: usize,
     img: &RgbImage,
     settings: &Settings,
 ) -> String {
-    // Pre-allocate a reasonable capacity for the row string to reduce reallocations.
-    // An average ANSI escape sequence is roughly 15 bytes.
-    let mut row_str = String::with_capacity(width_char * 15);
-    let y_px = y_char * 2;
-
-    // Pre-convert the sRGB palette to L*u*v* once per row if not in truecolor mode.
-    let paletted_colors = if settings.colors.is_truecolor {
-        None
-    } else {
-        Some(
-            settings
-                .colors
-                .palette
-                .iter()
-                .map(|&c| Srgb::new(c.0[0], c.0[1], c.0[2]).into_format())
-                .map(LuvColor::from_color_unclamped)
-                .collect::<Vec<_>>(),
-        )
-    };
+    let cells: Vec<_> = process_row_with_edges(y_char, width_char, img, settings, None)
+        .into_iter()
+        .map(Some)
+        .collect();
+    canvas::render_row(&cells, settings.advanced.compression)
+}
 
-    // State tracking for compression of ANSI escape sequences.
-    let mut last_fg: Option<RGB8> = None;
-    let mut last_bg: Option<RGB8> = None;
-
-    for x_char in 0..width_char {
-        let x_px = x_char * 2;
-
-        // Extract the 2x2 pixel block and convert to L*u*v*.
-        // The loops are constructed to guarantee these `get_pixel` calls are in-bounds.
-        let colors = [
-            pixel_to_luv(*img.get_pixel(x_px as u32, y_px as u32)),
-            pixel_to_luv(*img.get_pixel(x_px as u32 + 1, y_px as u32)),
-            pixel_to_luv(*img.get_pixel(x_px as u32, y_px as u32 + 1)),
-            pixel_to_luv(*img.get_pixel(x_px as u32 + 1, y_px as u32 + 1)),
-        ];
-
-        // Retrieve raw color data (Options)
-        let (character, fg, bg) = if let CharacterMode::Unicode(charset) = settings.characters.mode
-        {
-            process_unicode(
-                &colors,
-                charset,
-                settings.characters.color_mode,
-                paletted_colors.as_ref(),
-            )
-        } else {
-            let char_set: &[char] = match &settings.characters.mode {
-                CharacterMode::Ascii(cs) => cs.as_slice(),
-                CharacterMode::Custom(v) => v,
-                CharacterMode::Unicode(_) => unreachable!(),
-            };
-            process_ascii(
-                &colors,
-                char_set,
-                settings.characters.color_mode,
-                paletted_colors.as_ref(),
-            )
-        };
+/// Processes a single character row of the output image, drawing detected
+/// edges.
+///
+/// This is [`process_row`] with an [`EdgeMap`]: ASCII and custom characters on
+/// detected edges are replaced with the corresponding edge glyph. The row is
+/// returned as styled cells, which are turned into ANSI escape sequences by
+/// [`Canvas::render`](crate::canvas::Canvas::render).
+pub fn process_row_with_edges(
+    y_char: usize,
+    width_char: usize,
+    img: &RgbImage,
+    settings: &Settings,
+    edges: Option<&EdgeMap>,
+) -> Vec<Cell> {
+    let palette = (!settings.colors.is_truecolor)
+        .then(|| PaletteIndex::new(&palette_to_luv(&settings.colors.palette)));
+    process_row_with_palette(y_char, width_char, img, settings, palette.as_ref(), edges)
+}
 
-        // --- Compression ---
-
-        // Write the code if the color changed OR if compression is disabled.
-        let write_fg = fg != last_fg || !settings.advanced.compression;
-        let write_bg = bg != last_bg || !settings.advanced.compression;
-
-        // 1. Handle Foreground Change
-        if write_fg {
-            match fg {
-                // ANSI truecolor foreground: \x1b[38;2;R;G;Bm
-                Some(c) => write!(row_str, "\x1b[38;2;{};{};{}m", c.0, c.1, c.2).unwrap(),
-                // Reset foreground only: \x1b[39m
-                None => write!(row_str, "\x1b[39m").unwrap(),
-            }
-            last_fg = fg;
-        }
+/// Processes a single character row of the output image with a prepared
+/// palette.
+///
+/// This is [`process_row_with_edges`] for callers that process many rows with
+/// the same settings. `palette` is the color palette converted with
+/// [`palette_to_luv`] and indexed with [`PaletteIndex::new`], or `None` in
+/// truecolor mode.
+pub fn process_row_with_palette(
+    y_char: usize,
+    width_char: usize,
+    img: &RgbImage,
+    settings: &Settings,
+    palette: Option<&PaletteIndex>,
+    edges: Option<&EdgeMap>,
+) -> Vec<Cell> {
+    let mut row = Vec::with_capacity(width_char);
+    let y_px = y_char * 2;
 
-        // 2. Handle Background Change
-        if write_bg {
-            match bg {
-                // ANSI truecolor background: \x1b[48;2;R;G;Bm
-                Some(c) => write!(row_str, "\x1b[48;2;{};{};{}m", c.0, c.1, c.2).unwrap(),
-                // Reset background only: \x1b[49m
-                None => write!(row_str, "\x1b[49m").unwrap(),
-            }
-            last_bg = bg;
+    // Convert the two pixel rows of this character row to L*u*v* up front, into
+    // a buffer each thread reuses for its rows.
+    let row_len = img.width() as usize * 3;
+    let start = (y_px * row_len).min(img.as_raw().len());
+    let end = (start + 2 * row_len).min(img.as_raw().len());
+    LUV_ROW.with_borrow_mut(|luv_pixels| {
+        luv_pixels.clear();
+        luv::row_to_luv(&img.as_raw()[start..end], luv_pixels);
+        let (top, bottom) = luv_pixels.split_at(luv_pixels.len() / 2);
+
+        for x_char in 0..width_char {
+            let x_px = x_char * 2;
+
+            // Take the 2x2 pixel block.
+            // The image is always twice the grid size, so these indices are in-bounds.
+            let colors = [top[x_px], top[x_px + 1], bottom[x_px], bottom[x_px + 1]];
+
+            // Retrieve raw color data (Options)
+            let (character, fg, bg) =
+                if let CharacterMode::Unicode(charset) = settings.characters.mode {
+                    process_unicode(
+                        &colors,
+                        charset,
+                        settings.characters.color_mode,
+                        palette,
+                        settings.advanced.linear_light,
+                    )
+                } else {
+                    let char_set: &[char] = match &settings.characters.mode {
+                        CharacterMode::Ascii(cs) => cs.as_slice(),
+                        CharacterMode::Custom(v) => v,
+                        CharacterMode::Unicode(_) => unreachable!(),
+                    };
+                    let (character, fg, bg) = process_ascii(
+                        &colors,
+                        char_set,
+                        settings.characters.color_mode,
+                        palette,
+                        settings.advanced.linear_light,
+                    );
+                    let character = edges
+                        .and_then(|e| e.glyph(x_char, y_char))
+                        .unwrap_or(character);
+                    (character, fg, bg)
+                };
+
+            row.push(Cell {
+                character,
+                fg: fg.map(|(r, g, b)| Rgb([r, g, b])),
+                bg: bg.map(|(r, g, b)| Rgb([r, g, b])),
+            });
         }
+    });
 
-        // 3. Write the character
-        row_str.push(character);
-    }
-
-    // Reset everything at the end of the line so the terminal doesn't bleed colors
-    row_str.push_str("\x1b[0m");
-    row_str
+    row
 }
 
 /// Determines the best character and style for an ASCII/Custom character block.
 ///
 /// This mode uses brightness ramps to select an appropriate character from the
-/// provided character set.
+/// provided character set. If `linear` is `true`, block colors are averaged in
+/// linear-light RGB.
 fn process_ascii(
     colors: &[LuvColor; 4],
     char_set: &[char],
     color_mode: ColorMode,
-    palette: Option<&ColorPalette<LuvColor>>,
+    palette: Option<&PaletteIndex>,
+    linear: bool,
 ) -> (char, Option<RGB8>, Option<RGB8>) {
     if color_mode == ColorMode::TwoColor {
         let (lightest, darkest) = find_lightest_darkest(colors);
@@ -175,7 +203,7 @@ fn process_ascii(
             find_closest_pair(lightest, darkest, p, true)
         });
 
-        let avg = average_color(colors);
+        let avg = average_color(colors, linear);
         let total_dist = luv_distance(lightest, darkest);
         let avg_dist = luv_distance(avg, darkest);
 
@@ -193,7 +221,7 @@ fn process_ascii(
         )
     } else {
         // OneColor mode
-        let avg_color = average_color(colors);
+        let avg_color = average_color(colors, linear);
         let fg_luv = palette.map_or(avg_color, |p| find_closest(avg_color, p));
 
         let brightness = 1.0 - (luv_distance(fg_luv, BLACK_LUV) / 100.0).min(1.0);
@@ -211,85 +239,36 @@ fn process_ascii(
 ///
 /// This mode attempts to find the best-fitting block character by testing
 /// several candidates and choosing the one with the lowest perceptual color
-/// distance from the original 2x2 pixel block.
+/// distance from the original 2x2 pixel block. If `linear` is `true`, block
+/// colors are averaged in linear-light RGB.
 fn process_unicode(
     colors: &[LuvColor; 4],
     charset: UnicodeCharSet,
     color_mode: ColorMode,
-    palette: Option<&ColorPalette<LuvColor>>,
+    palette: Option<&PaletteIndex>,
+    linear: bool,
 ) -> (char, Option<RGB8>, Option<RGB8>) {
     // Fast path for solid block characters, which don't need complex candidate testing.
     if charset == UnicodeCharSet::Full {
-        let avg_color = average_color(colors);
+        let avg_color = average_color(colors, linear);
         let final_color = palette.map_or(avg_color, |p| find_closest(avg_color, p));
         // Full block is just FG color
         return ('█', Some(luv_to_rgb(final_color)), None);
     }
 
-    // Generate candidate characters and their ideal foreground/background colors.
-    let candidates = match charset {
-        UnicodeCharSet::Full => vec![('█', average_color(colors), BLACK_LUV)],
-        UnicodeCharSet::Half => {
-            vec![(
-                '▀',
-                average_color(&colors[0..2]),
-                average_color(&colors[2..4]),
-            )]
-        }
-        UnicodeCharSet::Quarter => vec![
-            (
-                '▀',
-                average_color(&colors[0..2]),
-                average_color(&colors[2..4]),
-            ), // Top half
-            (
-                '▐',
-                average_color(&[colors[1], colors[3]]),
-                average_color(&[colors[0], colors[2]]),
-            ), // Right half
-            (
-                '▞',
-                average_color(&[colors[1], colors[2]]),
-                average_color(&[colors[0], colors[3]]),
-            ), // Diagonal
-            (
-                '▖',
-                colors[2],
-                average_color(&[colors[0], colors[1], colors[3]]),
-            ), // Bottom-left
-            (
-                '▘',
-                colors[0],
-                average_color(&[colors[1], colors[2], colors[3]]),
-            ), // Top-left
-            (
-                '▝',
-                colors[1],
-                average_color(&[colors[0], colors[2], colors[3]]),
-            ), // Top-right
-            (
-                '▗',
-                colors[3],
-                average_color(&[colors[0], colors[1], colors[2]]),
-            ), // Bottom-right
-        ],
-        UnicodeCharSet::Shade => vec![
-            (' ', BLACK_LUV, BLACK_LUV),
-            ('░', average_color(colors), BLACK_LUV), // Light shade
-            ('▒', average_color(colors), BLACK_LUV), // Medium shade
-            ('▓', average_color(colors), BLACK_LUV), // Dark shade
-        ],
-    };
-
     // Find the candidate that best represents the original 2x2 pixel block.
-    let (best_char, best_fg, best_bg) = candidates
-        .into_iter()
-        .map(|(char_candidate, fg_candidate, bg_candidate)| {
+    // Each candidate's ideal colors are the averages of the sub-pixels it paints
+    // with its foreground and background.
+    let (best_char, best_fg, best_bg) = glyphs(charset)
+        .iter()
+        .map(|glyph| {
+            let fg_candidate = mix(colors, glyph.fg, linear);
+            let bg_candidate = mix(colors, glyph.bg, linear);
             let (fg, bg) = palette.map_or((fg_candidate, bg_candidate), |p| {
                 find_closest_pair(fg_candidate, bg_candidate, p, false)
             });
-            let dist = calculate_block_distance(colors, fg, bg, char_candidate);
-            (dist, char_candidate, fg, bg)
+            let dist = calculate_block_distance(colors, fg, bg, glyph.character);
+            (dist, glyph.character, fg, bg)
         })
         .min_by(|a, b| a.0.total_cmp(&b.0))
         .map_or((' ', BLACK_LUV, BLACK_LUV), |(_, c, fg, bg)| (c, fg, bg));
@@ -308,7 +287,7 @@ fn process_unicode(
 ///
 /// The formula is: $\sqrt{\Delta L^2 + \Delta u^2 + \Delta v^2}$
 #[inline]
-fn luv_distance(c1: LuvColor, c2: LuvColor) -> f32 {
+pub(crate) fn luv_distance(c1: LuvColor, c2: LuvColor) -> f32 {
     let (l1, u1, v1) = c1.into_components();
     let (l2, u2, v2) = c2.into_components();
     let dl = l1 - l2;
@@ -329,51 +308,149 @@ fn calculate_block_distance(
     bg: LuvColor,
     character: char,
 ) -> f32 {
-    let (c1, c2, c3, c4) = (original[0], original[1], original[2], original[3]);
-
-    // Map the character to its corresponding 2x2 color pattern.
-    let (t1, t2, t3, t4) = match character {
-        '▀' => (fg, fg, bg, bg),
-        '▐' => (bg, fg, bg, fg),
-        '▞' => (bg, fg, fg, bg),
-        '▖' => (bg, bg, fg, bg),
-        '▘' => (fg, bg, bg, bg),
-        '▝' => (bg, fg, bg, bg),
-        '▗' => (bg, bg, bg, fg),
-        '█' => (fg, fg, fg, fg),
-        '░' => (
-            blend(fg, bg, 0.25),
-            blend(fg, bg, 0.25),
-            blend(fg, bg, 0.25),
-            blend(fg, bg, 0.25),
-        ),
-        '▒' => (
-            blend(fg, bg, 0.50),
-            blend(fg, bg, 0.50),
-            blend(fg, bg, 0.50),
-            blend(fg, bg, 0.50),
-        ),
-        '▓' => (
-            blend(fg, bg, 0.75),
-            blend(fg, bg, 0.75),
-            blend(fg, bg, 0.75),
-            blend(fg, bg, 0.75),
-        ),
-        _ => (bg, bg, bg, bg), // Includes space ' '
-    };
-
-    let d1 = luv_distance(c1, t1);
-    let d2 = luv_distance(c2, t2);
-    let d3 = luv_distance(c3, t3);
-    let d4 = luv_distance(c4, t4);
+    let pattern = block_pattern(character, fg, bg);
+    let [d1, d2, d3, d4] = std::array::from_fn(|i| luv_distance(original[i], pattern[i]));
 
     // Return sum of squared distances.
     d4.mul_add(d4, d3.mul_add(d3, d1.mul_add(d1, d2 * d2)))
 }
 
+/// A candidate block character, with the sub-pixels it shows in its foreground
+/// and background colors.
+///
+/// Sub-pixels are numbered top-left, top-right, bottom-left, bottom-right.
+/// Glyphs that leave a color unused have no sub-pixels for it, which stands for
+/// black.
+struct Glyph {
+    /// The block character.
+    character: char,
+    /// The sub-pixels averaged for the foreground color.
+    fg: &'static [usize],
+    /// The sub-pixels averaged for the background color.
+    bg: &'static [usize],
+}
+
+/// The candidates for [`UnicodeCharSet::Full`].
+const FULL_GLYPHS: [Glyph; 1] = [Glyph {
+    character: '█',
+    fg: &[0, 1, 2, 3],
+    bg: &[],
+}];
+/// The candidates for [`UnicodeCharSet::Half`].
+const HALF_GLYPHS: [Glyph; 1] = [Glyph {
+    character: '▀',
+    fg: &[0, 1],
+    bg: &[2, 3],
+}];
+/// The candidates for [`UnicodeCharSet::Quarter`]: halves, the diagonal and
+/// single quadrants.
+const QUARTER_GLYPHS: [Glyph; 7] = [
+    Glyph {
+        character: '▀',
+        fg: &[0, 1],
+        bg: &[2, 3],
+    },
+    Glyph {
+        character: '▐',
+        fg: &[1, 3],
+        bg: &[0, 2],
+    },
+    Glyph {
+        character: '▞',
+        fg: &[1, 2],
+        bg: &[0, 3],
+    },
+    Glyph {
+        character: '▖',
+        fg: &[2],
+        bg: &[0, 1, 3],
+    },
+    Glyph {
+        character: '▘',
+        fg: &[0],
+        bg: &[1, 2, 3],
+    },
+    Glyph {
+        character: '▝',
+        fg: &[1],
+        bg: &[0, 2, 3],
+    },
+    Glyph {
+        character: '▗',
+        fg: &[3],
+        bg: &[0, 1, 2],
+    },
+];
+/// The candidates for [`UnicodeCharSet::Shade`].
+const SHADE_GLYPHS: [Glyph; 4] = [
+    Glyph {
+        character: ' ',
+        fg: &[],
+        bg: &[],
+    },
+    Glyph {
+        character: '░',
+        fg: &[0, 1, 2, 3],
+        bg: &[],
+    },
+    Glyph {
+        character: '▒',
+        fg: &[0, 1, 2, 3],
+        bg: &[],
+    },
+    Glyph {
+        character: '▓',
+        fg: &[0, 1, 2, 3],
+        bg: &[],
+    },
+];
+
+/// Returns the candidate characters of a Unicode character set.
+const fn glyphs(charset: UnicodeCharSet) -> &'static [Glyph] {
+    match charset {
+        UnicodeCharSet::Full => &FULL_GLYPHS,
+        UnicodeCharSet::Half => &HALF_GLYPHS,
+        UnicodeCharSet::Quarter => &QUARTER_GLYPHS,
+        UnicodeCharSet::Shade => &SHADE_GLYPHS,
+    }
+}
+
+/// Averages the colors of the given sub-pixels of a block, or returns black
+/// for none.
+#[inline]
+fn mix(colors: &[LuvColor; 4], pixels: &[usize], linear: bool) -> LuvColor {
+    if let [i] = pixels {
+        return colors[*i];
+    }
+    let picked: [LuvColor; 4] =
+        std::array::from_fn(|k| pixels.get(k).map_or(BLACK_LUV, |&i| colors[i]));
+    average_color(&picked[..pixels.len()], linear)
+}
+
+/// Maps a block character to the colors of the 2x2 sub-pixels it displays, in
+/// top-left, top-right, bottom-left, bottom-right order.
+///
+/// Characters without a block pattern, including the space, show only `bg`.
+pub(crate) fn block_pattern(character: char, fg: LuvColor, bg: LuvColor) -> [LuvColor; 4] {
+    match character {
+        '▀' => [fg, fg, bg, bg],
+        '▐' => [bg, fg, bg, fg],
+        '▞' => [bg, fg, fg, bg],
+        '▖' => [bg, bg, fg, bg],
+        '▘' => [fg, bg, bg, bg],
+        '▝' => [bg, fg, bg, bg],
+        '▗' => [bg, bg, bg, fg],
+        '█' => [fg; 4],
+        '░' => [blend(fg, bg, 0.25); 4],
+        '▒' => [blend(fg, bg, 0.50); 4],
+        '▓' => [blend(fg, bg, 0.75); 4],
+        _ => [bg; 4], // Includes space ' '
+    }
+}
+
 /// Linearly interpolates between two colors by a given ratio.
 #[inline]
-fn blend(a: LuvColor, b: LuvColor, ratio: f32) -> LuvColor {
+pub(crate) fn blend(a: LuvColor, b: LuvColor, ratio: f32) -> LuvColor {
     Luv::new(
         a.l.mul_add(ratio, b.l * (1.0 - ratio)),
         a.u.mul_add(ratio, b.u * (1.0 - ratio)),
@@ -382,12 +459,28 @@ fn blend(a: LuvColor, b: LuvColor, ratio: f32) -> LuvColor {
 }
 
 /// Computes the average color from a slice of L*u*v* colors.
+///
+/// If `linear` is `true`, the colors are averaged in linear-light RGB rather than
+/// in L*u*v*, which better preserves the total light of small bright details.
 #[inline]
-fn average_color(colors: &[LuvColor]) -> LuvColor {
+fn average_color(colors: &[LuvColor], linear: bool) -> LuvColor {
     let count = colors.len() as f32;
     if count == 0.0 {
         return BLACK_LUV;
     }
+    if linear {
+        let (r_sum, g_sum, b_sum) = colors
+            .iter()
+            .map(|&c| LinSrgb::from_color_unclamped(c))
+            .fold((0.0, 0.0, 0.0), |(r, g, b), c| {
+                (r + c.red, g + c.green, b + c.blue)
+            });
+        return LuvColor::from_color_unclamped(LinSrgb::new(
+            r_sum / count,
+            g_sum / count,
+            b_sum / count,
+        ));
+    }
     let (l_sum, u_sum, v_sum) = colors
         .iter()
         .fold((0.0, 0.0, 0.0), |(l, u, v), c| (l + c.l, u + c.u, v + c.v));
@@ -411,16 +504,8 @@ fn find_lightest_darkest(colors: &[LuvColor]) -> (LuvColor, LuvColor) {
 }
 
 /// Finds the single closest color in a palette to a given color.
-fn find_closest(color: LuvColor, palette: &ColorPalette<LuvColor>) -> LuvColor {
-    palette
-        .iter()
-        .min_by(|&&c1, &&c2| {
-            let d1 = luv_distance(color, c1);
-            let d2 = luv_distance(color, c2);
-            d1.total_cmp(&d2)
-        })
-        .copied()
-        .unwrap_or(color)
+fn find_closest(color: LuvColor, palette: &PaletteIndex) -> LuvColor {
+    palette.nearest(color).map_or(color, |(_, c)| c)
 }
 
 /// Finds the two best-matching colors from a palette for a given pair of colors.
@@ -430,47 +515,26 @@ fn find_closest(color: LuvColor, palette: &ColorPalette<LuvColor>) -> LuvColor {
 fn find_closest_pair(
     color1: LuvColor,
     color2: LuvColor,
-    palette: &ColorPalette<LuvColor>,
+    palette: &PaletteIndex,
     order_by_brightness: bool,
 ) -> (LuvColor, LuvColor) {
-    if palette.is_empty() {
+    let (Some((idx1, closest1)), Some(first)) = (palette.nearest(color1), palette.get(0)) else {
         return (BLACK_LUV, BLACK_LUV);
-    }
+    };
     if palette.len() == 1 {
-        return (palette[0], palette[0]);
+        return (first, first);
     }
 
     // For Unicode characters, spatial position matters more than brightness. Find the
     // closest color for fg and bg independently without ensuring they are distinct.
     if !order_by_brightness {
-        return (find_closest(color1, palette), find_closest(color2, palette));
+        return (closest1, find_closest(color2, palette));
     }
 
     // For ASCII brightness ramps, find the best two *distinct* colors from the palette.
-    let (mut closest1, mut min_dist1, mut idx1) = (palette[0], f32::MAX, 0);
-
-    for (i, &p_color) in palette.iter().enumerate() {
-        let dist = luv_distance(color1, p_color);
-        if dist < min_dist1 {
-            min_dist1 = dist;
-            closest1 = p_color;
-            idx1 = i;
-        }
-    }
-
-    let mut closest2 = if idx1 == 0 { palette[1] } else { palette[0] };
-    let mut min_dist2 = f32::MAX;
-
-    for (i, &p_color) in palette.iter().enumerate() {
-        if i == idx1 {
-            continue; // Ensure the second color is from a different palette entry.
-        }
-        let dist = luv_distance(color2, p_color);
-        if dist < min_dist2 {
-            min_dist2 = dist;
-            closest2 = p_color;
-        }
-    }
+    let closest2 = palette
+        .nearest_excluding(color2, idx1)
+        .map_or(first, |(_, c)| c);
 
     // Return the pair as (foreground, background), where the background is the color
     // perceptually closer to black.
@@ -491,7 +555,7 @@ fn brightness_to_char_index(brightness: f32, char_set_len: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::brightness_to_char_index;
+    use super::{BLACK_LUV, LuvColor, average_color, brightness_to_char_index};
 
     #[test]
     fn brightness_index_bounds() {
@@ -500,4 +564,13 @@ mod tests {
         assert_eq!(brightness_to_char_index(-0.1, 10), 0);
         assert_eq!(brightness_to_char_index(1.1, 10), 9);
     }
+
+    #[test]
+    fn linear_average_is_brighter() {
+        let white = LuvColor::new(100.0, 0.0, 0.0);
+        let perceptual = average_color(&[BLACK_LUV, white], false);
+        let linear = average_color(&[BLACK_LUV, white], true);
+        assert!((perceptual.l - 50.0).abs() < 1e-3);
+        assert!(linear.l > 70.0);
+    }
 }
@@ -0,0 +1,95 @@
+//! Progress reporting and cancellation for long conversions.
+//!
+//! A [`Converter`](crate::Converter) can be given a callback that is told how
+//! many character rows are done, and a [`CancellationToken`] that stops the
+//! conversion between rows. Both are checked while the rows are processed,
+//! which is where nearly all of the time of a large conversion goes.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// A callback receiving the number of character rows completed and the total
+/// number of rows.
+///
+/// Rows are processed in parallel, so the callback may be called from several
+/// threads at once, and calls may arrive slightly out of order. Each count is
+/// reported exactly once, so a completed conversion always reports all rows.
+pub type ProgressCallback = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// A shared flag to cancel running conversions from another thread.
+///
+/// Clones share the same flag, so a token can be handed to a converter and
+/// kept by the code that decides to cancel it. Once cancelled, a token stays
+/// cancelled and every conversion using it fails with
+/// [`AnsiImageError::Cancelled`](crate::error::AnsiImageError::Cancelled).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every conversion using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use image::{DynamicImage, RgbImage};
+
+    use super::CancellationToken;
+    use crate::{
+        Converter,
+        error::AnsiImageError,
+        settings::{Settings, SizeMode},
+    };
+
+    #[test]
+    fn reports_progress_and_cancels() {
+        let mut settings = Settings::default();
+        settings.size.width = 4;
+        settings.size.height = 6;
+        settings.size.mode = SizeMode::Exact;
+        let img = DynamicImage::ImageRgb8(RgbImage::new(16, 16));
+
+        let (calls, last) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (c, l) = (calls.clone(), last.clone());
+        let token = CancellationToken::new();
+        let mut converter = Converter::new(settings)
+            .unwrap()
+            .with_progress(move |done, total| {
+                assert_eq!(total, 6);
+                c.fetch_add(1, Ordering::Relaxed);
+                l.fetch_max(done, Ordering::Relaxed);
+            })
+            .with_cancellation(token.clone());
+
+        converter.convert_image(&img).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 6);
+        assert_eq!(last.load(Ordering::Relaxed), 6);
+
+        token.cancel();
+        assert!(matches!(
+            converter.convert_image(&img),
+            Err(AnsiImageError::Cancelled)
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+//! Serde helpers for settings fields whose types do not implement serde traits.
+//!
+//! Colors are written as `#rrggbb` hex strings, which keeps palettes in
+//! configuration files short and readable, and resize filters are written as
+//! their snake-case names. Optional colors use `"default"` for the terminal's
+//! default color, since a missing field means the setting's default instead.
+
+use fast_image_resize::FilterType;
+use image::Rgb;
+use serde::{Deserialize as _, Deserializer, Serializer, de::Error as _, ser::Error as _};
+
+/// Formats a color as a `#rrggbb` hex string.
+fn to_hex(Rgb([r, g, b]): Rgb<u8>) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Parses a `#rrggbb` (or `rrggbb`) hex string into a color.
+fn from_hex(s: &str) -> Result<Rgb<u8>, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("invalid color `{s}`, expected `#rrggbb`"));
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .map_err(|_| format!("invalid color `{s}`, expected `#rrggbb`"))
+    };
+    Ok(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+/// Serializes a color as a hex string.
+pub(crate) mod rgb_hex {
+    use super::{Deserializer, Rgb, Serializer, from_hex, to_hex};
+    use serde::{Deserialize as _, de::Error as _};
+
+    pub(crate) fn serialize<S: Serializer>(
+        color: &Rgb<u8>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(*color))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Rgb<u8>, D::Error> {
+        from_hex(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Serializes an optional color as a hex string, or `"default"` for `None`.
+pub(crate) mod option_rgb_hex {
+    use super::{Deserializer, Rgb, Serializer, from_hex, to_hex};
+    use serde::{Deserialize as _, de::Error as _};
+
+    /// The name written for the terminal's default color.
+    const DEFAULT: &str = "default";
+
+    pub(crate) fn serialize<S: Serializer>(
+        color: &Option<Rgb<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match color {
+            Some(color) => serializer.serialize_str(&to_hex(*color)),
+            None => serializer.serialize_str(DEFAULT),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Rgb<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .filter(|s| s != DEFAULT)
+            .map(|s| from_hex(&s).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// Serializes a list of colors as hex strings.
+pub(crate) mod vec_rgb_hex {
+    use super::{Deserializer, Rgb, Serializer, from_hex, to_hex};
+    use serde::{Deserialize as _, de::Error as _, ser::SerializeSeq as _};
+
+    pub(crate) fn serialize<S: Serializer>(
+        colors: &[Rgb<u8>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(colors.len()))?;
+        for &color in colors {
+            seq.serialize_element(&to_hex(color))?;
+        }
+        seq.end()
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Rgb<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| from_hex(s).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+/// The names of the serializable resize filters.
+const RESIZE_FILTERS: [(&str, FilterType); 7] = [
+    ("box", FilterType::Box),
+    ("bilinear", FilterType::Bilinear),
+    ("hamming", FilterType::Hamming),
+    ("catmull_rom", FilterType::CatmullRom),
+    ("mitchell", FilterType::Mitchell),
+    ("gaussian", FilterType::Gaussian),
+    ("lanczos3", FilterType::Lanczos3),
+];
+
+/// Serializes a resize filter as its snake-case name. Custom filters cannot be
+/// serialized.
+pub(crate) fn serialize_resize_filter<S: Serializer>(
+    filter: &FilterType,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let (name, _) = RESIZE_FILTERS
+        .iter()
+        .find(|(_, f)| f == filter)
+        .ok_or_else(|| S::Error::custom("custom resize filters cannot be serialized"))?;
+    serializer.serialize_str(name)
+}
+
+/// Deserializes a resize filter from its snake-case name.
+pub(crate) fn deserialize_resize_filter<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<FilterType, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    RESIZE_FILTERS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, filter)| filter)
+        .ok_or_else(|| {
+            let names: Vec<&str> = RESIZE_FILTERS.iter().map(|(n, _)| *n).collect();
+            D::Error::custom(format!(
+                "unknown resize filter `{name}`, expected one of {}",
+                names.join(", ")
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use crate::settings::{CharacterMode, Equalization, Frame, Overlay, Settings, UnicodeCharSet};
+
+    #[test]
+    fn settings_toml_round_trip() {
+        let mut settings = Settings::default();
+        settings.characters.mode = CharacterMode::Unicode(UnicodeCharSet::Quarter);
+        settings.colors.is_truecolor = false;
+        settings.colors.palette = vec![Rgb([0x1a, 0x1c, 0x2c]), Rgb([255, 255, 255])];
+        settings.adjustments.equalization = Equalization::Clahe {
+            clip_limit: 3.0,
+            tiles: 8,
+        };
+        settings.frame = Frame {
+            is_enabled: true,
+            color: Some(Rgb([255, 0, 0])),
+            ..Default::default()
+        };
+        settings.advanced.resize_filter = fast_image_resize::FilterType::CatmullRom;
+        // `None` colors whose default is a color must survive the round trip.
+        settings.overlays = vec![Overlay {
+            text: "title".to_owned(),
+            fg: None,
+            background: Some(Rgb([0, 0, 0])),
+            ..Default::default()
+        }];
+
+        let text = toml::to_string(&settings).unwrap();
+        assert!(text.contains(r##"palette = ["#1a1c2c", "#ffffff"]"##));
+        assert!(text.contains(r#"resize_filter = "catmull_rom""#));
+        assert!(text.contains(r#"fg = "default""#));
+        let parsed: Settings = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string(&parsed).unwrap(), text);
+        assert_eq!(parsed.overlays[0].fg, None);
+        assert_eq!(parsed.frame.color, Some(Rgb([255, 0, 0])));
+
+        // Missing fields fall back to their defaults.
+        let partial: Settings = toml::from_str(
+            "[characters]\nmode = { unicode = \"half\" }\n[advanced]\nresize_filter = \"box\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            partial.characters.mode,
+            CharacterMode::Unicode(UnicodeCharSet::Half)
+        );
+        assert_eq!(partial.size.width, Settings::default().size.width);
+        assert!(toml::from_str::<Settings>("[colors]\npalette = [\"#12345\"]").is_err());
+        // Misspelled fields are reported instead of ignored.
+        assert!(toml::from_str::<Settings>("[size]\nwidht = 40").is_err());
+    }
+}
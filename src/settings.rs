@@ -2,6 +2,7 @@
 
 use fast_image_resize::FilterType as ResizeFilter;
 use image::Rgb;
+use serde::{Deserialize, Serialize};
 
 /// A type alias for a color palette, represented as a vector of RGB colors.
 pub type ColorPalette<C> = Vec<C>;
@@ -10,14 +11,23 @@ pub type ColorPalette<C> = Vec<C>;
 ///
 /// This struct aggregates all settings related to size, characters, colors,
 /// and advanced algorithms.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Settings {
     /// Sizing and dimension settings.
     pub size: Size,
+    /// Geometric transforms, such as cropping and zooming, applied to the source image.
+    pub transform: Transform,
     /// Character set and rendering style settings.
     pub characters: Characters,
     /// Color palette and mode settings.
     pub colors: Colors,
+    /// Image adjustments applied to the resized image before character selection.
+    pub adjustments: Adjustments,
+    /// Text labels drawn on top of the output, in order.
+    pub overlays: Vec<Overlay>,
+    /// An optional border drawn around the output.
+    pub frame: Frame,
     /// Advanced options like resizing and dithering algorithms.
     pub advanced: Advanced,
 }
@@ -26,28 +36,322 @@ impl Default for Settings {
     /// Creates a default `Settings` configuration.
     ///
     /// - **Size**: 80x40 characters, fitting while preserving aspect ratio.
+    /// - **Transform**: The whole, EXIF-oriented image, without zoom.
     /// - **Characters**: Full ASCII set, two-color mode, 0.5 aspect ratio.
     /// - **Colors**: Truecolor enabled.
-    /// - **Advanced**: Lanczos3 resize filter, dithering enabled.
+    /// - **Adjustments**: None (the image is used as-is).
+    /// - **Overlays**: None.
+    /// - **Frame**: No border.
+    /// - **Advanced**: Lanczos3 resize filter, no sharpening, dithering enabled.
     fn default() -> Self {
         Self {
             size: Size::default(),
+            transform: Transform::default(),
             characters: Characters::default(),
             colors: Colors::default(),
+            adjustments: Adjustments::default(),
+            overlays: vec![],
+            frame: Frame::default(),
             advanced: Advanced::default(),
         }
     }
 }
 
+impl Settings {
+    /// Returns a [`SettingsBuilder`] starting from the default settings.
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    /// Checks the settings for invalid values and combinations.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of every problem found, not just the first one.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_owned());
+            }
+        };
+
+        let size = &self.size;
+        check(
+            size.width > 0 || size.mode == SizeMode::Height,
+            "Width must be at least 1.",
+        );
+        check(
+            size.height > 0 || size.mode == SizeMode::Width,
+            "Height must be at least 1.",
+        );
+
+        let palette = &self.colors.palette;
+        check(
+            self.colors.is_truecolor || !palette.is_empty(),
+            "A color palette must be selected when not in truecolor mode.",
+        );
+        check(
+            self.colors.is_truecolor
+                || palette.len() != 1
+                || self.characters.color_mode == ColorMode::OneColor
+                || matches!(self.characters.mode, CharacterMode::Unicode(_)),
+            "Two-color ASCII mode needs a palette of at least two colors.",
+        );
+
+        check(
+            !matches!(&self.characters.mode, CharacterMode::Custom(chars) if chars.is_empty()),
+            "Custom character mode requires at least one character.",
+        );
+        check(
+            self.characters.aspect_ratio > 0.0 && self.characters.aspect_ratio.is_finite(),
+            "The character aspect ratio must be finite and greater than zero.",
+        );
+        check(
+            self.characters.edges.threshold.is_finite(),
+            "The edge threshold must be finite.",
+        );
+
+        let adjustments = &self.adjustments;
+        check(
+            adjustments.brightness.is_finite(),
+            "Brightness adjustment must be finite.",
+        );
+        check(
+            adjustments.contrast.is_finite(),
+            "Contrast adjustment must be finite.",
+        );
+        check(
+            adjustments.gamma > 0.0 && adjustments.gamma.is_finite(),
+            "Gamma adjustment must be finite and greater than zero.",
+        );
+        check(
+            adjustments.saturation.is_finite(),
+            "Saturation adjustment must be finite.",
+        );
+        check(
+            adjustments.hue_rotation.is_finite(),
+            "Hue rotation must be finite.",
+        );
+        check(
+            !matches!(
+                adjustments.equalization,
+                Equalization::Clahe { clip_limit, tiles }
+                    if !(clip_limit > 0.0 && clip_limit.is_finite()) || tiles == 0
+            ),
+            "CLAHE requires a positive clip limit and at least one tile.",
+        );
+        check(self.transform.zoom >= 1.0, "Zoom must be at least 1.0.");
+        check(
+            self.transform.pan_x.is_finite() && self.transform.pan_y.is_finite(),
+            "Pan offsets must be finite.",
+        );
+
+        let sharpen = &self.advanced.sharpen;
+        check(
+            !sharpen.is_enabled
+                || (sharpen.radius > 0.0
+                    && sharpen.radius.is_finite()
+                    && sharpen.amount >= 0.0
+                    && sharpen.amount.is_finite()),
+            "Sharpening requires a finite positive radius and a finite non-negative amount.",
+        );
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// A fluent builder for [`Settings`].
+///
+/// Every method changes one setting of the defaults; fields without a method
+/// can be set with [`SettingsBuilder::with`]. [`SettingsBuilder::build`]
+/// validates the result.
+///
+/// ```
+/// use ansimage::{Settings, UnicodeCharSet, palettes, settings::CharacterMode};
+///
+/// let settings = Settings::builder()
+///     .width(100)
+///     .character_mode(CharacterMode::Unicode(UnicodeCharSet::Quarter))
+///     .palette(palettes::COLOR_PALETTE_SWEETIE16)
+///     .dithering(false)
+///     .build()
+///     .unwrap();
+/// assert!(!settings.colors.is_truecolor);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct SettingsBuilder {
+    settings: Settings,
+}
+
+impl SettingsBuilder {
+    /// Sets the target width in characters.
+    pub const fn width(mut self, width: usize) -> Self {
+        self.settings.size.width = width;
+        self
+    }
+
+    /// Sets the target height in characters.
+    pub const fn height(mut self, height: usize) -> Self {
+        self.settings.size.height = height;
+        self
+    }
+
+    /// Sets how the image is fitted into the width and height.
+    pub const fn size_mode(mut self, mode: SizeMode) -> Self {
+        self.settings.size.mode = mode;
+        self
+    }
+
+    /// Sets whether small images may be scaled up.
+    pub const fn allow_upscale(mut self, allow_upscale: bool) -> Self {
+        self.settings.size.allow_upscale = allow_upscale;
+        self
+    }
+
+    /// Sets the padding of the output to the full width and height.
+    pub const fn padding(mut self, padding: Padding) -> Self {
+        self.settings.size.padding = padding;
+        self
+    }
+
+    /// Sets the geometric transforms applied to the source image.
+    pub const fn transform(mut self, transform: Transform) -> Self {
+        self.settings.transform = transform;
+        self
+    }
+
+    /// Sets the character mode.
+    pub fn character_mode(mut self, mode: CharacterMode) -> Self {
+        self.settings.characters.mode = mode;
+        self
+    }
+
+    /// Sets whether background colors are used.
+    pub const fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.settings.characters.color_mode = color_mode;
+        self
+    }
+
+    /// Sets the width-to-height ratio of a terminal character.
+    pub const fn aspect_ratio(mut self, aspect_ratio: f32) -> Self {
+        self.settings.characters.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    /// Sets the edge detection overlay.
+    pub const fn edges(mut self, edges: Edges) -> Self {
+        self.settings.characters.edges = edges;
+        self
+    }
+
+    /// Enables truecolor output.
+    pub const fn truecolor(mut self) -> Self {
+        self.settings.colors.is_truecolor = true;
+        self
+    }
+
+    /// Quantizes colors to a palette, turning truecolor output off.
+    pub fn palette(mut self, palette: &[Rgb<u8>]) -> Self {
+        self.settings.colors.palette = palette.to_vec();
+        self.settings.colors.is_truecolor = false;
+        self
+    }
+
+    /// Sets the image adjustments.
+    pub const fn adjustments(mut self, adjustments: Adjustments) -> Self {
+        self.settings.adjustments = adjustments;
+        self
+    }
+
+    /// Adds a text overlay.
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.settings.overlays.push(overlay);
+        self
+    }
+
+    /// Sets the frame drawn around the output.
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.settings.frame = frame;
+        self
+    }
+
+    /// Sets the resampling filter.
+    pub const fn resize_filter(mut self, filter: ResizeFilter) -> Self {
+        self.settings.advanced.resize_filter = filter;
+        self
+    }
+
+    /// Sets whether resizing and averaging happen in linear light.
+    pub const fn linear_light(mut self, linear_light: bool) -> Self {
+        self.settings.advanced.linear_light = linear_light;
+        self
+    }
+
+    /// Sets the sharpening applied after resizing.
+    pub const fn sharpen(mut self, sharpen: Sharpen) -> Self {
+        self.settings.advanced.sharpen = sharpen;
+        self
+    }
+
+    /// Enables or disables dithering.
+    pub const fn dithering(mut self, is_enabled: bool) -> Self {
+        self.settings.advanced.dithering.is_enabled = is_enabled;
+        self
+    }
+
+    /// Enables or disables ANSI color code compression.
+    pub const fn compression(mut self, compression: bool) -> Self {
+        self.settings.advanced.compression = compression;
+        self
+    }
+
+    /// Sets the number of threads to convert with, or `0` for one per core.
+    pub const fn threads(mut self, threads: usize) -> Self {
+        self.settings.advanced.threads = threads;
+        self
+    }
+
+    /// Changes any other setting with a closure.
+    pub fn with(mut self, f: impl FnOnce(&mut Settings)) -> Self {
+        f(&mut self.settings);
+        self
+    }
+
+    /// Validates and returns the settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidSettings` error listing every problem found by
+    /// [`Settings::validate`].
+    pub fn build(self) -> crate::error::Result<Settings> {
+        self.settings.validate().map_err(|problems| {
+            crate::error::AnsiImageError::InvalidSettings(problems.join(" "))
+        })?;
+        Ok(self.settings)
+    }
+}
+
 /// Defines the target dimensions and sizing mode for the output.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Size {
     /// The target width in terminal character cells.
     pub width: usize,
     /// The target height in terminal character cells.
     pub height: usize,
-    /// The sizing strategy (`Fit` or `Exact`).
+    /// The sizing strategy (`Fit`, `Fill`, `Exact`, `Width` or `Height`).
     pub mode: SizeMode,
+    /// If `false`, images too small to fill the requested size are rendered at
+    /// their native resolution instead of being scaled up.
+    pub allow_upscale: bool,
+    /// Padding of the output up to exactly `width` x `height` characters.
+    pub padding: Padding,
 }
 
 impl Default for Size {
@@ -56,12 +360,15 @@ impl Default for Size {
             width: 80,
             height: 40,
             mode: SizeMode::Fit,
+            allow_upscale: true,
+            padding: Padding::default(),
         }
     }
 }
 
 /// Specifies how to interpret the `width` and `height` fields in [`Size`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SizeMode {
     /// Scale the image to fit within the `width` and `height` bounds while
     /// preserving the original aspect ratio.
@@ -69,10 +376,203 @@ pub enum SizeMode {
     /// Stretch or shrink the image to the exact `width` and `height`,
     /// potentially altering the aspect ratio.
     Exact,
+    /// Scale the image to cover the whole `width` and `height` while preserving
+    /// the original aspect ratio, cropping the overflow evenly from both sides.
+    Fill,
+    /// Scale the image to exactly `width`, deriving the height from the aspect
+    /// ratio. The `height` field is ignored.
+    Width,
+    /// Scale the image to exactly `height`, deriving the width from the aspect
+    /// ratio. The `width` field is ignored.
+    Height,
+}
+
+/// Configures padding of the output to a fixed canvas size.
+///
+/// Modes such as [`SizeMode::Fit`] produce output smaller than the requested
+/// box in one dimension. When padding is enabled, the character grid is placed
+/// on a canvas of at least [`Size::width`] x [`Size::height`] cells, which keeps
+/// the output size predictable for dashboards and layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Padding {
+    /// Set to `true` to enable padding.
+    pub is_enabled: bool,
+    /// The horizontal placement of the image within the canvas.
+    pub horizontal: Alignment,
+    /// The vertical placement of the image within the canvas.
+    pub vertical: Alignment,
+    /// How to fill the cells around the image.
+    pub fill: PaddingFill,
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            horizontal: Alignment::Center,
+            vertical: Alignment::Center,
+            fill: PaddingFill::Default,
+        }
+    }
+}
+
+/// The placement of content along one axis of a larger area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    /// Align to the left or top.
+    Start,
+    /// Center the content.
+    Center,
+    /// Align to the right or bottom.
+    End,
+}
+
+impl Alignment {
+    /// Returns the offset of content of length `content` within a length of `total`.
+    #[must_use]
+    pub const fn offset(&self, content: usize, total: usize) -> usize {
+        let free = total.saturating_sub(content);
+        match self {
+            Self::Start => 0,
+            Self::Center => free / 2,
+            Self::End => free,
+        }
+    }
+}
+
+/// How padding cells are filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaddingFill {
+    /// Blank cells using the terminal's default colors.
+    Default,
+    /// Blank cells with a solid background color.
+    #[serde(with = "crate::serialization::rgb_hex")]
+    Color(Rgb<u8>),
+    /// Transparent cells, which leave existing terminal content visible.
+    Transparent,
+}
+
+/// Geometric transforms applied to the source image before resizing.
+///
+/// The image is first rotated and flipped. The crop then selects a region of the
+/// reoriented image, and the zoom and pan select a viewport within that region.
+/// Output dimensions are calculated from the final viewport rather than the full
+/// image.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Transform {
+    /// If `true`, [`convert`](crate::convert) applies the EXIF orientation tag
+    /// of the image file, so photos taken in portrait mode appear upright.
+    pub auto_orient: bool,
+    /// A clockwise rotation applied after the EXIF orientation.
+    pub rotation: Rotation,
+    /// If `true`, mirrors the image horizontally (after rotation).
+    pub flip_horizontal: bool,
+    /// If `true`, mirrors the image vertically (after rotation).
+    pub flip_vertical: bool,
+    /// The region of the source image to render.
+    pub crop: Crop,
+    /// The zoom factor within the cropped region. `1.0` shows the whole region,
+    /// and `2.0` shows half its width and height. Must be at least `1.0`.
+    pub zoom: f32,
+    /// Horizontal pan of the zoomed viewport, from `-1.0` (left edge of the
+    /// region) through `0.0` (centered) to `1.0` (right edge).
+    pub pan_x: f32,
+    /// Vertical pan of the zoomed viewport, from `-1.0` (top edge of the region)
+    /// through `0.0` (centered) to `1.0` (bottom edge).
+    pub pan_y: f32,
+}
+
+impl Transform {
+    /// Sets the zoom factor and pans the viewport so it is centered as close as
+    /// possible to the given point, in normalized (`0.0..=1.0`) region coordinates.
+    #[must_use]
+    pub fn zoom_at(mut self, zoom: f32, center_x: f32, center_y: f32) -> Self {
+        self.zoom = zoom;
+        // The viewport center can move within [half, 1 - half] of the region.
+        let pan = |center: f32| {
+            let half = 0.5 / zoom.max(1.0);
+            let travel = 0.5 - half;
+            if travel <= f32::EPSILON {
+                0.0
+            } else {
+                ((center - 0.5) / travel).clamp(-1.0, 1.0)
+            }
+        };
+        self.pan_x = pan(center_x);
+        self.pan_y = pan(center_y);
+        self
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            auto_orient: true,
+            rotation: Rotation::None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            crop: Crop::None,
+            zoom: 1.0,
+            pan_x: 0.0,
+            pan_y: 0.0,
+        }
+    }
+}
+
+/// A clockwise rotation in multiples of 90 degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    /// No rotation.
+    None,
+    /// Rotate 90 degrees clockwise.
+    Rotate90,
+    /// Rotate 180 degrees.
+    Rotate180,
+    /// Rotate 270 degrees clockwise (90 degrees counter-clockwise).
+    Rotate270,
+}
+
+/// Selects a rectangular region of the source image.
+///
+/// Regions extending past the image bounds are clipped to the image.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Crop {
+    /// Use the whole image.
+    None,
+    /// A rectangle in source image pixels.
+    Pixels {
+        /// The left edge of the region.
+        x: u32,
+        /// The top edge of the region.
+        y: u32,
+        /// The width of the region.
+        width: u32,
+        /// The height of the region.
+        height: u32,
+    },
+    /// A rectangle in normalized coordinates, where `0.0` is the left/top edge of
+    /// the image and `1.0` is the right/bottom edge.
+    Normalized {
+        /// The left edge of the region.
+        x: f32,
+        /// The top edge of the region.
+        y: f32,
+        /// The width of the region.
+        width: f32,
+        /// The height of the region.
+        height: f32,
+    },
 }
 
 /// Configures the character set, color usage, and aspect ratio compensation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Characters {
     /// The primary character mode to use for rendering.
     pub mode: CharacterMode,
@@ -84,6 +584,8 @@ pub struct Characters {
     /// roughly twice as tall as they are wide. This is used to correct the
     /// image aspect ratio.
     pub aspect_ratio: f32,
+    /// Edge detection overlay for ASCII and custom character modes.
+    pub edges: Edges,
 }
 
 impl Default for Characters {
@@ -92,12 +594,77 @@ impl Default for Characters {
             mode: CharacterMode::Ascii(AsciiCharSet::All),
             color_mode: ColorMode::TwoColor,
             aspect_ratio: 0.5,
+            edges: Edges::default(),
+        }
+    }
+}
+
+/// Configures the edge detection overlay.
+///
+/// When enabled, cells lying on a strong edge have their brightness-ramp character
+/// replaced with a line-drawing glyph oriented along the edge. This only applies to
+/// [`CharacterMode::Ascii`] and [`CharacterMode::Custom`].
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Edges {
+    /// Set to `true` to enable the edge overlay.
+    pub is_enabled: bool,
+    /// The edge detection algorithm to use.
+    pub detector: EdgeDetector,
+    /// The minimum edge strength (`0.0` to `1.0`) required to draw a glyph.
+    /// Lower values draw more edges.
+    pub threshold: f32,
+    /// The set of glyphs used to draw edges.
+    pub style: EdgeStyle,
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            detector: EdgeDetector::Sobel,
+            threshold: 0.25,
+            style: EdgeStyle::Ascii,
+        }
+    }
+}
+
+/// Edge detection algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeDetector {
+    /// A Sobel operator on the image lightness. Fast, but sensitive to noise and
+    /// fine texture.
+    Sobel,
+    /// A difference of Gaussians followed by a Sobel operator. Suppresses texture
+    /// and noise, producing cleaner outlines.
+    DifferenceOfGaussians,
+}
+
+/// Glyph sets used to draw edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeStyle {
+    /// Plain ASCII line characters (`|`, `_`, `/`, `\`).
+    Ascii,
+    /// Unicode box-drawing characters (`│`, `─`, `╱`, `╲`).
+    BoxDrawing,
+}
+
+impl EdgeStyle {
+    /// Returns the vertical, horizontal, rising diagonal, and falling diagonal glyphs.
+    #[must_use]
+    pub const fn glyphs(&self) -> [char; 4] {
+        match self {
+            Self::Ascii => ['|', '_', '/', '\\'],
+            Self::BoxDrawing => ['│', '─', '╱', '╲'],
         }
     }
 }
 
 /// Defines which set of characters to use for rendering.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CharacterMode {
     /// Use a predefined set of standard ASCII characters, chosen based on brightness.
     Ascii(AsciiCharSet),
@@ -109,7 +676,8 @@ pub enum CharacterMode {
 }
 
 /// Predefined sets of ASCII characters, ordered by perceived brightness.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum AsciiCharSet {
     /// All available ASCII characters from the brightness ramp.
     All,
@@ -138,7 +706,8 @@ impl AsciiCharSet {
 }
 
 /// Predefined sets of Unicode block-drawing characters for higher-fidelity output.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UnicodeCharSet {
     /// Solid block (`█`), using only the average color of each 2x2 block.
     Full,
@@ -152,7 +721,8 @@ pub enum UnicodeCharSet {
 }
 
 /// Determines whether to use both foreground and background colors.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ColorMode {
     /// Use only a foreground color. The background will be the terminal's default.
     OneColor,
@@ -161,13 +731,15 @@ pub enum ColorMode {
 }
 
 /// Configures the color palette and mode for the output.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Colors {
     /// If `true`, output 24-bit RGB ("truecolor") ANSI escape codes. This
     /// provides the highest color fidelity.
     pub is_truecolor: bool,
     /// A palette of colors to quantize the image to if `is_truecolor` is `false`.
     /// Required for terminals that do not support truecolor.
+    #[serde(with = "crate::serialization::vec_rgb_hex")]
     pub palette: ColorPalette<Rgb<u8>>,
 }
 
@@ -180,25 +752,300 @@ impl Default for Colors {
     }
 }
 
+/// Tonal and color adjustments applied to the image before conversion.
+///
+/// Adjustments are applied to the resized image (not the source), so their cost
+/// scales with the output size. They run in the following order: invert,
+/// brightness, contrast, gamma, saturation, hue rotation, grayscale, and
+/// finally lightness equalization.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Adjustments {
+    /// Additive brightness offset, where `-1.0` is fully black, `0.0` is unchanged,
+    /// and `1.0` is fully white.
+    pub brightness: f32,
+    /// Contrast multiplier around mid-gray. `1.0` is unchanged, values below
+    /// `1.0` flatten the image, and values above `1.0` increase contrast.
+    pub contrast: f32,
+    /// Gamma correction exponent. `1.0` is unchanged, values above `1.0`
+    /// brighten the midtones, and values below `1.0` darken them. Must be positive.
+    pub gamma: f32,
+    /// Saturation multiplier. `0.0` removes all color, `1.0` is unchanged, and
+    /// values above `1.0` make colors more vivid.
+    pub saturation: f32,
+    /// Hue rotation in degrees.
+    pub hue_rotation: f32,
+    /// If `true`, inverts all colors.
+    pub invert: bool,
+    /// If `true`, converts the image to grayscale.
+    pub grayscale: bool,
+    /// Histogram equalization applied to the L* (lightness) channel, which
+    /// spreads low-contrast images across the full brightness range.
+    pub equalization: Equalization,
+}
+
+impl Adjustments {
+    /// Returns `true` if these adjustments leave the image unchanged.
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            saturation: 1.0,
+            hue_rotation: 0.0,
+            invert: false,
+            grayscale: false,
+            equalization: Equalization::None,
+        }
+    }
+}
+
+/// Specifies how to equalize the lightness histogram of the image.
+///
+/// Equalization is computed over the resized image, so every character in an
+/// ASCII brightness ramp is actually used even for low-contrast images.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Equalization {
+    /// No equalization.
+    None,
+    /// Global histogram equalization over the whole image.
+    Global,
+    /// Contrast-limited adaptive histogram equalization (CLAHE).
+    ///
+    /// The image is split into a grid of tiles that are equalized individually
+    /// and blended together, which preserves local detail better than global
+    /// equalization.
+    Clahe {
+        /// The maximum height of a histogram bin, as a multiple of the average
+        /// bin height. Lower values limit noise amplification; `1.0` disables
+        /// equalization entirely. A typical value is `2.0` to `4.0`.
+        clip_limit: f32,
+        /// The number of tiles along each axis. A typical value is `8`.
+        tiles: u32,
+    },
+}
+
+/// A text label drawn on top of the output, such as a file name or watermark.
+///
+/// The text block is anchored to an edge or the center of the canvas on each
+/// axis. Multi-line text is split on `\n`, and each line is aligned within the
+/// block according to `horizontal`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Overlay {
+    /// The text to draw. Each character occupies one cell.
+    pub text: String,
+    /// The horizontal anchor of the text block within the canvas.
+    pub horizontal: Alignment,
+    /// The vertical anchor of the text block within the canvas.
+    pub vertical: Alignment,
+    /// The distance in cells from the anchored left or right edge. Ignored when
+    /// centered.
+    pub margin_x: usize,
+    /// The distance in cells from the anchored top or bottom edge. Ignored when
+    /// centered.
+    pub margin_y: usize,
+    /// The text color, or `None` for the terminal's default.
+    #[serde(with = "crate::serialization::option_rgb_hex")]
+    pub fg: Option<Rgb<u8>>,
+    /// If set, the text is drawn on a solid box of this color. Otherwise, each
+    /// character keeps the background color of the cell it covers.
+    #[serde(with = "crate::serialization::option_rgb_hex")]
+    pub background: Option<Rgb<u8>>,
+    /// Blank cells added to the left and right of the text inside the box.
+    /// Ignored without a `background`.
+    pub box_padding: usize,
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            horizontal: Alignment::Start,
+            vertical: Alignment::Start,
+            margin_x: 0,
+            margin_y: 0,
+            fg: Some(Rgb([255, 255, 255])),
+            background: None,
+            box_padding: 1,
+        }
+    }
+}
+
+/// Configures a border drawn around the output.
+///
+/// The frame takes up one cell on each side, and the image is sized to fit
+/// within the remaining space so the framed output still fits in [`Size`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Frame {
+    /// Set to `true` to draw the frame.
+    pub is_enabled: bool,
+    /// The line style of the frame.
+    pub style: FrameStyle,
+    /// The color of the frame and its text, or `None` for the terminal's default.
+    #[serde(with = "crate::serialization::option_rgb_hex")]
+    pub color: Option<Rgb<u8>>,
+    /// An optional title drawn in the top edge of the frame.
+    pub title: Option<String>,
+    /// An optional caption drawn in the bottom edge of the frame.
+    pub caption: Option<String>,
+    /// The horizontal placement of the title and caption.
+    pub text_alignment: Alignment,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            style: FrameStyle::Single,
+            color: None,
+            title: None,
+            caption: None,
+            text_alignment: Alignment::Center,
+        }
+    }
+}
+
+/// Line styles for [`Frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameStyle {
+    /// Thin single lines (`┌─┐`).
+    Single,
+    /// Double lines (`╔═╗`).
+    Double,
+    /// Thin lines with rounded corners (`╭─╮`).
+    Rounded,
+    /// Thick lines (`┏━┓`).
+    Heavy,
+    /// Plain ASCII characters (`+-+`).
+    Ascii,
+}
+
+impl FrameStyle {
+    /// Returns the top-left, top-right, bottom-left, and bottom-right corners,
+    /// followed by the horizontal and vertical line glyphs.
+    #[must_use]
+    pub const fn glyphs(&self) -> [char; 6] {
+        match self {
+            Self::Single => ['┌', '┐', '└', '┘', '─', '│'],
+            Self::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            Self::Rounded => ['╭', '╮', '╰', '╯', '─', '│'],
+            Self::Heavy => ['┏', '┓', '┗', '┛', '━', '┃'],
+            Self::Ascii => ['+', '+', '+', '+', '-', '|'],
+        }
+    }
+}
+
+/// Layout settings for rendering several images as a grid of tiles.
+///
+/// Every tile is converted with the same [`Settings`] and centered in a cell as
+/// large as the biggest tile. See [`convert_montage`](crate::convert_montage).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Montage {
+    /// The number of tiles per row. Must be at least `1`.
+    pub columns: usize,
+    /// The number of blank columns between neighboring tiles.
+    pub column_gap: usize,
+    /// The number of blank rows between rows of tiles.
+    pub row_gap: usize,
+    /// Set to `true` to draw each tile's label in a row below it.
+    pub labels: bool,
+    /// The color of the labels, or `None` for the terminal's default.
+    #[serde(with = "crate::serialization::option_rgb_hex")]
+    pub label_color: Option<Rgb<u8>>,
+}
+
+impl Default for Montage {
+    fn default() -> Self {
+        Self {
+            columns: 3,
+            column_gap: 2,
+            row_gap: 1,
+            labels: true,
+            label_color: None,
+        }
+    }
+}
+
 /// Advanced settings for image processing algorithms.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Advanced {
     /// The resampling filter to use when resizing the image.
     /// `Lanczos3` is a high-quality default.
+    #[serde(
+        serialize_with = "crate::serialization::serialize_resize_filter",
+        deserialize_with = "crate::serialization::deserialize_resize_filter"
+    )]
     pub resize_filter: ResizeFilter,
+    /// If `true`, resizing and block color averaging are done in linear-light RGB
+    /// instead of on gamma-encoded sRGB values. This keeps thin bright details
+    /// (such as stars or highlights) from being darkened or lost when downscaling.
+    pub linear_light: bool,
+    /// Sharpening applied right after resizing to recover detail lost when
+    /// downscaling.
+    pub sharpen: Sharpen,
     /// Dithering configuration.
     pub dithering: Dithering,
     /// If true, only emits ANSI codes when colors change.
     /// If false, emits codes for every character (larger output).
     pub compression: bool,
+    /// The number of threads to convert with. `0` uses rayon's global thread
+    /// pool, which has one thread per core. Other counts use a pool shared by
+    /// all conversions with the same count. Pools for the four most recently
+    /// used counts are kept alive between conversions; the others are released
+    /// once no converter holds them. Without the `parallel` feature,
+    /// conversions always run on the calling thread and this is ignored.
+    pub threads: usize,
 }
 
 impl Default for Advanced {
     fn default() -> Self {
         Self {
             resize_filter: ResizeFilter::Lanczos3,
+            linear_light: false,
+            sharpen: Sharpen::default(),
             dithering: Dithering::default(),
             compression: true,
+            threads: 0,
+        }
+    }
+}
+
+/// Configures the unsharp-mask sharpening stage.
+///
+/// Sharpening runs on the resized image, before adjustments and quantization. It
+/// is most useful for small outputs, where downscaling blurs away fine detail.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sharpen {
+    /// Set to `true` to enable sharpening.
+    pub is_enabled: bool,
+    /// The standard deviation of the Gaussian blur, in resized pixels. Larger
+    /// values sharpen coarser features. Must be positive.
+    pub radius: f32,
+    /// The strength of the effect. `0.0` has no effect, and `1.0` adds the full
+    /// difference between the image and its blurred copy.
+    pub amount: f32,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self {
+            is_enabled: false,
+            radius: 1.0,
+            amount: 0.5,
         }
     }
 }
@@ -207,7 +1054,8 @@ impl Default for Advanced {
 ///
 /// Dithering is a technique used to create the illusion of more colors when
 /// working with a limited palette. It is only applied when not in truecolor mode.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Dithering {
     /// Set to `true` to enable dithering.
     pub is_enabled: bool,
@@ -231,7 +1079,8 @@ impl Default for Dithering {
 /// **Note**: This is currently a placeholder for future extension, as the
 /// backend `imagequant` uses its own internal ordered dithering logic which
 /// is not selectable beyond on/off.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DitherMatrix {
     /// Floyd-Steinberg error-diffusion dithering algorithm.
     FloydSteinberg,
@@ -242,3 +1091,69 @@ pub enum DitherMatrix {
     /// Burkes error-diffusion dithering algorithm.
     Burkes,
 }
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{AsciiCharSet, CharacterMode, Settings, SizeMode};
+
+    #[test]
+    fn validate_reports_every_problem() {
+        assert!(Settings::default().validate().is_ok());
+
+        let problems = Settings::builder()
+            .width(0)
+            .character_mode(CharacterMode::Custom(vec![]))
+            .aspect_ratio(-1.0)
+            .palette(&[Rgb([255, 255, 255])])
+            .settings
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            problems,
+            [
+                "Width must be at least 1.",
+                "Two-color ASCII mode needs a palette of at least two colors.",
+                "Custom character mode requires at least one character.",
+                "The character aspect ratio must be finite and greater than zero.",
+            ]
+        );
+
+        // Every comparison with NaN is false, so range checks alone can let it
+        // through. Each floating-point setting is also checked for being finite.
+        let mut settings = Settings::default();
+        settings.characters.aspect_ratio = f32::NAN;
+        settings.adjustments.brightness = f32::NAN;
+        settings.adjustments.contrast = f32::INFINITY;
+        settings.adjustments.saturation = f32::NAN;
+        settings.adjustments.hue_rotation = f32::NAN;
+        settings.transform.pan_x = f32::NAN;
+        settings.characters.edges.threshold = f32::NAN;
+        settings.advanced.sharpen.is_enabled = true;
+        settings.advanced.sharpen.amount = f32::NAN;
+        assert_eq!(
+            settings.validate().unwrap_err(),
+            [
+                "The character aspect ratio must be finite and greater than zero.",
+                "The edge threshold must be finite.",
+                "Brightness adjustment must be finite.",
+                "Contrast adjustment must be finite.",
+                "Saturation adjustment must be finite.",
+                "Hue rotation must be finite.",
+                "Pan offsets must be finite.",
+                "Sharpening requires a finite positive radius and a finite non-negative amount.",
+            ]
+        );
+
+        // The height is ignored when sizing by width only.
+        let settings = Settings::builder()
+            .height(0)
+            .size_mode(SizeMode::Width)
+            .character_mode(CharacterMode::Ascii(AsciiCharSet::All))
+            .build()
+            .unwrap();
+        assert_eq!(settings.size.height, 0);
+        assert!(Settings::builder().palette(&[]).build().is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! WebAssembly bindings for converting images in the browser.
+//!
+//! Build a `cdylib` with `--no-default-features --features wasm` for the
+//! `wasm32-unknown-unknown` target and generate the JavaScript glue with
+//! `wasm-bindgen`. Images are passed as the bytes of an encoded file, such as
+//! the contents of a `File` or a `fetch` response, and settings as a JSON
+//! string with the same structure as a configuration file. Missing settings
+//! keep their defaults, so `"{}"` (or an empty string) converts with the
+//! default settings.
+//!
+//! ```js
+//! import init, { convert, convertToHtml } from "./pkg/ansimage.js";
+//!
+//! await init();
+//! const bytes = new Uint8Array(await file.arrayBuffer());
+//! preview.innerHTML = convertToHtml(bytes, JSON.stringify({ size: { width: 120 } }));
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Settings, error::AnsiImageError};
+
+/// Converts an encoded image into a string of ANSI escape sequences, for
+/// terminal emulators such as xterm.js.
+///
+/// # Errors
+///
+/// Throws if the settings JSON is invalid or the image cannot be converted.
+#[wasm_bindgen]
+pub fn convert(bytes: &[u8], settings_json: &str) -> Result<String, JsError> {
+    let settings = parse_settings(settings_json)?;
+    Ok(crate::convert_bytes(bytes, &settings)?)
+}
+
+/// Converts an encoded image into an HTML `<pre>` element with inline colors.
+///
+/// See [`Canvas::render_html`](crate::Canvas::render_html).
+///
+/// # Errors
+///
+/// Throws if the settings JSON is invalid or the image cannot be converted.
+#[wasm_bindgen(js_name = convertToHtml)]
+pub fn convert_to_html(bytes: &[u8], settings_json: &str) -> Result<String, JsError> {
+    let settings = parse_settings(settings_json)?;
+    Ok(crate::convert_bytes_to_canvas(bytes, &settings)?.render_html())
+}
+
+/// Parses settings from JSON, treating an empty string as the defaults.
+fn parse_settings(json: &str) -> Result<Settings, AnsiImageError> {
+    if json.trim().is_empty() {
+        return Ok(Settings::default());
+    }
+    serde_json::from_str(json).map_err(|e| AnsiImageError::InvalidSettings(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, RgbImage};
+
+    use super::{convert_to_html, parse_settings};
+
+    #[test]
+    fn converts_encoded_bytes() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, image::Rgb([255, 0, 0])))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let html = convert_to_html(&png, r#"{"size": {"width": 2, "height": 1}}"#)
+            .unwrap_or_else(|_| panic!("conversion failed"));
+        assert!(html.starts_with("<pre class=\"ansimage\"><span style=\"color:#ff0000;"));
+        assert_eq!(parse_settings("").unwrap().size.width, 80);
+        assert!(parse_settings("{\"size\": 3}").is_err());
+    }
+}
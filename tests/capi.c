@@ -0,0 +1,69 @@
+/* Exercises the C API through include/ansimage.h; run by tests/capi.rs. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "ansimage.h"
+
+#define CHECK(cond)                                                    \
+  do {                                                                 \
+    if (!(cond)) {                                                     \
+      const char *error = ansimage_last_error_message();               \
+      fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n",    \
+              __FILE__, __LINE__, #cond, error ? error : "none");      \
+      return 1;                                                        \
+    }                                                                  \
+  } while (0)
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s IMAGE\n", argv[0]);
+    return 2;
+  }
+
+  AnsimageSettings *settings = NULL;
+  CHECK(ansimage_settings_from_json(
+            "{\"size\": {\"width\": 4, \"height\": 2, \"mode\": \"exact\"}}",
+            &settings) == ANSIMAGE_STATUS_OK);
+  CHECK(settings != NULL);
+
+  /* A 2x2 image: red, green, blue and white. */
+  const uint8_t pixels[] = {255, 0,   0, 255, 0,   255, 0,   255,
+                            0,   0, 255, 255, 255, 255, 255, 255};
+  char *output = NULL;
+  CHECK(ansimage_convert_rgba_buffer(pixels, sizeof pixels, 2, 2, settings,
+                                     &output) == ANSIMAGE_STATUS_OK);
+  CHECK(output != NULL && strstr(output, "\x1b[") != NULL);
+  ansimage_free_string(output);
+
+  output = NULL;
+  CHECK(ansimage_convert_file(argv[1], settings, &output) ==
+        ANSIMAGE_STATUS_OK);
+  CHECK(output != NULL && strlen(output) > 0);
+  ansimage_free_string(output);
+
+  /* Errors map to status codes and leave a message behind. */
+  CHECK(ansimage_convert_rgba_buffer(pixels, sizeof pixels - 1, 2, 2, settings,
+                                     &output) ==
+        ANSIMAGE_STATUS_INVALID_ARGUMENT);
+  CHECK(ansimage_convert_rgba_buffer(pixels, sizeof pixels, UINT32_MAX,
+                                     UINT32_MAX, settings, &output) ==
+        ANSIMAGE_STATUS_INVALID_ARGUMENT);
+  CHECK(ansimage_convert_file(argv[1], settings, NULL) ==
+        ANSIMAGE_STATUS_INVALID_ARGUMENT);
+  CHECK(ansimage_convert_file("does/not/exist.png", NULL, &output) ==
+        ANSIMAGE_STATUS_IO);
+  CHECK(strstr(ansimage_last_error_message(), "I/O error") != NULL);
+
+  AnsimageSettings *invalid = NULL;
+  CHECK(ansimage_settings_from_json("{\"size\": {\"width\": 0}}", &invalid) ==
+        ANSIMAGE_STATUS_INVALID_SETTINGS);
+  CHECK(invalid == NULL);
+  CHECK(ansimage_settings_from_json("not json", &invalid) ==
+        ANSIMAGE_STATUS_INVALID_SETTINGS);
+
+  ansimage_settings_free(settings);
+  ansimage_settings_free(ansimage_settings_new());
+  ansimage_free_string(NULL);
+  return 0;
+}
@@ -0,0 +1,61 @@
+//! Builds the `cdylib` with the `capi` feature, compiles `tests/capi.c`
+//! against it and `include/ansimage.h`, and runs the program. Uses the C
+//! compiler from `CC`, or `cc`.
+
+#![cfg(all(feature = "capi", unix))]
+
+use std::{env, path::Path, process::Command};
+
+#[test]
+fn c_program_uses_the_c_api() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // The crate only builds an rlib by default. The shared library is built
+    // into its own target directory, because the one running this test is
+    // locked by the build.
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let status = Command::new(env!("CARGO"))
+        .args(["rustc", "--lib", "--crate-type", "cdylib"])
+        .args(["--no-default-features", "--features", "capi,parallel"])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "building the cdylib failed");
+    let lib_dir = target_dir.join("debug");
+
+    let program = lib_dir.join("capi_c_test");
+    let image = lib_dir.join("capi_c_test.png");
+    image::RgbImage::from_fn(16, 16, |x, y| image::Rgb([x as u8 * 16, y as u8 * 16, 128]))
+        .save(&image)
+        .unwrap();
+
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(compiler)
+        .arg(root.join("tests/capi.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .args(["-lansimage", "-Wall", "-Werror", "-o"])
+        .arg(&program)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling tests/capi.c failed");
+
+    // Cargo puts its own output directories on the library search path, and
+    // that takes precedence over the rpath.
+    let output = Command::new(&program)
+        .arg(&image)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}